@@ -0,0 +1,87 @@
+//! Compares `GismuScorer` with a straightforward scorer on an all-letters run.
+//!
+//! Run with `cargo run --release --example scoring_benchmark`.
+
+use rayon::prelude::*;
+use std::time::Instant;
+use vlazba::gismu_utils::{GismuGenerator, GismuScorer};
+use vlazba::libs::config::{C, DEFAULT_WEIGHTS_STR, V};
+
+/// Dynamic-programming LCS, allocating per call
+fn naive_lcs(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut current = vec![0; a.len() + 1];
+    for &cb in b {
+        let mut prev = 0;
+        for (i, &ca) in a.iter().enumerate() {
+            let temp = current[i + 1];
+            current[i + 1] = if ca == cb { prev + 1 } else { current[i + 1].max(current[i]) };
+            prev = temp;
+        }
+    }
+    current[a.len()]
+}
+
+/// Dyad rule rebuilding the interleaved source strings for every pair
+fn naive_dyad(candidate: &str, word: &str) -> bool {
+    let iw02: String = word.chars().step_by(2).collect();
+    let iw12: String = word.chars().skip(1).step_by(2).collect();
+    let l = candidate.len();
+    (0..l.saturating_sub(2)).any(|i| iw02.contains(&candidate[i..i + 2]) || iw12.contains(&candidate[i..i + 2]))
+        || (0..l.saturating_sub(1)).any(|i| word.contains(&candidate[i..i + 2]))
+}
+
+fn naive_score(candidate: &str, words: &[String], weights: &[f32]) -> f32 {
+    words
+        .iter()
+        .zip(weights)
+        .map(|(word, weight)| {
+            let score = match naive_lcs(candidate, word) {
+                0 | 1 => 0.0,
+                2 if naive_dyad(candidate, word) => 2.0,
+                2 => 0.0,
+                n => n as f32,
+            };
+            score / word.len() as f32 * weight
+        })
+        .sum()
+}
+
+fn main() {
+    let words: Vec<String> = ["uan", "rakan", "ekspekt", "esper", "predpologa", "mulud"]
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+    let weights: Vec<f32> = DEFAULT_WEIGHTS_STR
+        .split(',')
+        .map(|w| w.parse().unwrap())
+        .collect();
+    let letters = |s: &str| s.chars().map(|c| c.to_string()).collect::<Vec<_>>();
+    let shapes = vec!["ccvcv".to_string(), "cvccv".to_string()];
+    let candidates = GismuGenerator::new(letters(C), letters(V), shapes).iterator().unwrap();
+    println!("{} candidates, {} source words", candidates.len(), words.len());
+
+    let start = Instant::now();
+    let naive: Vec<f32> = candidates
+        .par_iter()
+        .map(|candidate| naive_score(candidate, &words, &weights))
+        .collect();
+    let naive_time = start.elapsed();
+
+    let scorer = GismuScorer::new(&words, &weights);
+    let start = Instant::now();
+    let fast: Vec<f32> = candidates
+        .par_iter()
+        .map(|candidate| scorer.compute_score_with_name(candidate).0)
+        .collect();
+    let fast_time = start.elapsed();
+
+    let mismatches = naive.iter().zip(&fast).filter(|(a, b)| a != b).count();
+    println!("naive scorer:  {:?}", naive_time);
+    println!("GismuScorer:   {:?}", fast_time);
+    println!(
+        "speedup:       {:.2}x",
+        naive_time.as_secs_f64() / fast_time.as_secs_f64()
+    );
+    println!("mismatches:    {}", mismatches);
+}
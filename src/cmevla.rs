@@ -0,0 +1,234 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::gismu_utils::GismuScorer;
+use crate::jvozba::jvozbanarge::{has_la_lai_doi, la_lai_doi_positions, LaLaiDoiRule};
+use crate::phonotactics::{
+    check_word_class, is_consonant, is_permissible_pair, is_valid_initial_pair, is_valid_vowel_pair, is_vowel,
+    normalize_letters, PhonotacticError, WordClass,
+};
+use crate::similarity::FeatureEditMetric;
+use crate::transliteration::Transliterator;
+
+/// Consonants appended to names ending in a vowel, in order of preference
+const FINAL_CONSONANTS: [char; 2] = ['s', 'n'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CmevlaError {
+    Phonotactics(PhonotacticError),
+    Empty,
+    /// Cmevla end in a consonant
+    VowelEnding,
+    /// `la`, `lai` or `doi` not preceded by a consonant
+    LaLaiDoi,
+}
+
+impl fmt::Display for CmevlaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CmevlaError::Phonotactics(e) => write!(f, "{}", e),
+            CmevlaError::Empty => write!(f, "cmevla has no letters"),
+            CmevlaError::VowelEnding => write!(f, "cmevla must end in a consonant"),
+            CmevlaError::LaLaiDoi => {
+                write!(f, "la, lai and doi inside a cmevla must follow a consonant")
+            }
+        }
+    }
+}
+
+impl Error for CmevlaError {}
+
+impl From<PhonotacticError> for CmevlaError {
+    fn from(e: PhonotacticError) -> Self {
+        CmevlaError::Phonotactics(e)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CmevlaCandidate {
+    pub word: String,
+    /// Similarity to the source name by feature-weighted edit distance,
+    /// ignoring commas
+    pub score: f32,
+}
+
+/// Builds cmevla from a foreign name, spelled by English rules unless
+/// another transliterator is given
+pub struct CmevlaGenerator {
+    name: String,
+    source: String,
+}
+
+impl CmevlaGenerator {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: normalize_letters(&Transliterator::English.transliterate(name)),
+        }
+    }
+
+    /// Transliterate the name with `transliterator` instead;
+    /// [`Transliterator::None`] keeps a name already written in Lojban letters
+    pub fn with_transliterator(mut self, transliterator: Transliterator) -> Self {
+        self.source = normalize_letters(&transliterator.transliterate(&self.name));
+        self
+    }
+
+    /// Name in Lojban letters that candidates are built from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Valid candidates ranked by similarity to the source name, then by
+    /// fewer commas and shorter words
+    pub fn candidates(&self) -> Vec<CmevlaCandidate> {
+        let sources = [self.source.clone()];
+        let scorer = GismuScorer::new(&sources, &[1.0]).with_metric(Box::new(FeatureEditMetric::default()));
+
+        let mut candidates: Vec<CmevlaCandidate> = cmevla_forms(&self.source)
+            .into_iter()
+            .filter(|word| check_cmevla(word).is_ok())
+            .map(|word| CmevlaCandidate {
+                score: scorer.score_candidate(word.replace(',', "")).score,
+                word,
+            })
+            .collect();
+        let commas = |word: &str| word.matches(',').count();
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| commas(&a.word).cmp(&commas(&b.word)))
+                .then_with(|| a.word.len().cmp(&b.word.len()))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        candidates
+    }
+}
+
+/// Check a cmevla: phonotactics, with commas standing between vowels like
+/// apostrophes, a consonant ending and no `la`, `lai` or `doi` after a vowel
+pub fn check_cmevla(word: &str) -> Result<(), CmevlaError> {
+    if word.is_empty() {
+        return Err(CmevlaError::Empty);
+    }
+    check_word_class(&word.replace(',', "'"), WordClass::Cmevla)?;
+    if !word.ends_with(is_consonant) {
+        return Err(CmevlaError::VowelEnding);
+    }
+    if has_la_lai_doi(word, LaLaiDoiRule::AfterVowel) {
+        return Err(CmevlaError::LaLaiDoi);
+    }
+    Ok(())
+}
+
+/// Variants to try: consonant endings, then clusters repaired and `la`, `lai`
+/// and `doi` broken up
+fn cmevla_forms(source: &str) -> BTreeSet<String> {
+    let mut forms = BTreeSet::new();
+    if source.is_empty() {
+        return forms;
+    }
+    if source.ends_with(is_vowel) {
+        forms.extend(FINAL_CONSONANTS.iter().map(|c| format!("{}{}", source, c)));
+        let dropped = &source[..source.len() - 1];
+        if dropped.ends_with(is_consonant) {
+            forms.insert(dropped.to_string());
+        }
+    } else {
+        forms.insert(source.to_string());
+    }
+
+    forms
+        .into_iter()
+        .map(|form| repair_clusters(&form))
+        .flat_map(|form| broken_forms(&form))
+        .map(|form| repair_clusters(&form))
+        .collect()
+}
+
+/// Insert `y` into every impermissible consonant pair and a comma into every
+/// impermissible vowel pair
+fn repair_clusters(form: &str) -> String {
+    let mut chars: Vec<char> = form.chars().collect();
+    let mut i = 0;
+    while i + 1 < chars.len() {
+        let (a, b) = (chars[i], chars[i + 1]);
+        if is_vowel(a) && is_vowel(b) && !is_valid_vowel_pair(a, b) {
+            chars.insert(i + 1, ',');
+        } else if is_consonant(a) && is_consonant(b) {
+            // Word-initial pairs and pairs after another consonant must be initials
+            let needs_initial = i == 0 || is_consonant(chars[i - 1]);
+            if !is_permissible_pair(a, b) || (needs_initial && !is_valid_initial_pair(a, b)) {
+                chars.insert(i + 1, 'y');
+            }
+        }
+        i += 1;
+    }
+    chars.into_iter().collect()
+}
+
+/// Forms without `la`, `lai` or `doi` after a vowel: the `l` or `d` replaced
+/// by `r` or `t`, or an `n` inserted before it
+fn broken_forms(form: &str) -> BTreeSet<String> {
+    let mut pending = vec![form.to_string()];
+    let mut forms = BTreeSet::new();
+    while let Some(form) = pending.pop() {
+        let Some(i) = la_lai_doi_positions(&form, LaLaiDoiRule::AfterVowel).next() else {
+            forms.insert(form);
+            continue;
+        };
+        let replacement = if form[i..].starts_with('l') { "r" } else { "t" };
+        pending.push(format!("{}{}{}", &form[..i], replacement, &form[i + 1..]));
+        if i > 0 {
+            pending.push(format!("{}n{}", &form[..i], &form[i..]));
+        }
+    }
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spells_names_by_english_rules_by_default() {
+        assert_eq!(CmevlaGenerator::new("Alexander").source(), "aleksander");
+        assert_eq!(CmevlaGenerator::new("Beijing").source(), "beidjin");
+        let lojban = CmevlaGenerator::new("Alexander").with_transliterator(Transliterator::None);
+        assert_eq!(lojban.source(), "alexander");
+
+        let candidates = CmevlaGenerator::new("Beijing").candidates();
+        assert_eq!(candidates[0].word, "beidjin");
+        assert!(candidates.iter().all(|c| check_cmevla(&c.word).is_ok()));
+    }
+
+    #[test]
+    fn checks_cmevla() {
+        assert_eq!(check_cmevla("alis"), Ok(()));
+        assert_eq!(check_cmevla(""), Err(CmevlaError::Empty));
+        assert_eq!(check_cmevla("alisa"), Err(CmevlaError::VowelEnding));
+        assert_eq!(check_cmevla("alabaman"), Err(CmevlaError::LaLaiDoi));
+        assert_eq!(check_cmevla("kamlais"), Ok(()));
+        assert!(matches!(check_cmevla("mlkan"), Err(CmevlaError::Phonotactics(_))));
+    }
+
+    #[test]
+    fn breaks_up_la_lai_doi() {
+        let candidates = CmevlaGenerator::new("alabama").with_transliterator(Transliterator::None).candidates();
+        assert!(!candidates.is_empty());
+        for candidate in &candidates {
+            assert!(!has_la_lai_doi(&candidate.word, LaLaiDoiRule::AfterVowel), "{}", candidate.word);
+        }
+    }
+
+    #[test]
+    fn jvozba_rule_is_stricter() {
+        assert!(has_la_lai_doi("kamlais", LaLaiDoiRule::Anywhere));
+        assert!(!has_la_lai_doi("kamlais", LaLaiDoiRule::AfterVowel));
+        assert!(has_la_lai_doi("lanbis", LaLaiDoiRule::Anywhere));
+        assert!(!has_la_lai_doi("selau", LaLaiDoiRule::AfterVowel));
+        assert_eq!(la_lai_doi_positions("sadoilas", LaLaiDoiRule::AfterVowel).collect::<Vec<_>>(), [2, 5]);
+    }
+}
@@ -0,0 +1,490 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::gismu_utils::GismuScorer;
+use crate::similarity::FeatureEditMetric;
+use crate::jvozba::rafsi_shape::rafsi_shape;
+use crate::jvozba::tools::search_selrafsi_from_rafsi2;
+use crate::libs::config::V;
+use crate::phonotactics::{
+    check_word, check_word_class, is_consonant, is_diphthong, is_forbidden_triple, is_permissible_pair,
+    is_valid_initial_pair, is_valid_vowel_pair, is_vowel, normalize_letters, PhonotacticError, WordClass,
+};
+
+/// Hyphens tried between a category rafsi and the root, in order of preference
+const STAGE3_HYPHENS: [char; 3] = ['r', 'n', 'l'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuhivlaStage {
+    /// Category rafsi, hyphen and root, e.g. `spatrkoka`
+    Three,
+    /// Root alone, adapted to brivla phonotactics
+    Four,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuhivlaError {
+    Phonotactics(PhonotacticError),
+    /// Brivla end in a vowel
+    ConsonantEnding,
+    /// Brivla need a consonant pair within their first five letters
+    NoEarlyCluster,
+    /// Brivla have at least two syllables
+    TooShort,
+    /// Word has the shape of a gismu
+    GismuShape,
+    /// Word splits into rafsi and would be read as a lujvo
+    LujvoShape,
+    /// A CV cmavo in front of the word would fuse with it into a lujvo
+    Slinkuhi,
+    /// Word falls apart into leading cmavo and a shorter brivla
+    Tosmabru,
+    /// Category word is not a five-letter gismu
+    InvalidCategory(String),
+}
+
+impl fmt::Display for FuhivlaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FuhivlaError::Phonotactics(e) => write!(f, "{}", e),
+            FuhivlaError::ConsonantEnding => write!(f, "fu'ivla must end in a vowel"),
+            FuhivlaError::NoEarlyCluster => {
+                write!(f, "fu'ivla need a consonant pair within the first five letters")
+            }
+            FuhivlaError::TooShort => write!(f, "fu'ivla need at least two syllables"),
+            FuhivlaError::GismuShape => write!(f, "word has the shape of a gismu"),
+            FuhivlaError::LujvoShape => write!(f, "word splits into rafsi like a lujvo"),
+            FuhivlaError::Slinkuhi => write!(f, "fails the slinku'i test"),
+            FuhivlaError::Tosmabru => write!(f, "falls apart into cmavo and a brivla"),
+            FuhivlaError::InvalidCategory(word) => {
+                write!(f, "category '{}' is not a CVCCV or CCVCV gismu", word)
+            }
+        }
+    }
+}
+
+impl Error for FuhivlaError {}
+
+impl From<PhonotacticError> for FuhivlaError {
+    fn from(e: PhonotacticError) -> Self {
+        FuhivlaError::Phonotactics(e)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FuhivlaCandidate {
+    pub word: String,
+    pub stage: FuhivlaStage,
+    /// Similarity of the root to the source word by feature-weighted edit distance
+    pub score: f32,
+}
+
+/// Builds stage-3 and stage-4 fu'ivla from a source word already written in
+/// Lojban letters
+pub struct FuhivlaGenerator {
+    source: String,
+    category: Option<String>,
+}
+
+impl FuhivlaGenerator {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: normalize_letters(source),
+            category: None,
+        }
+    }
+
+    /// Also build stage-3 forms prefixed with the four-letter rafsi of a gismu,
+    /// e.g. `spati` for plants
+    pub fn with_category(mut self, gismu: &str) -> Result<Self, FuhivlaError> {
+        let gismu = gismu.trim().to_lowercase();
+        if !rafsi_shape(&gismu).is_ok_and(|shape| shape.is_gismu()) {
+            return Err(FuhivlaError::InvalidCategory(gismu));
+        }
+        self.category = Some(gismu);
+        Ok(self)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Valid candidates ranked by similarity to the source word, shorter
+    /// words first on equal scores
+    pub fn candidates(&self) -> Vec<FuhivlaCandidate> {
+        let sources = [self.source.clone()];
+        let scorer = GismuScorer::new(&sources, &[1.0]).with_metric(Box::new(FeatureEditMetric::default()));
+        let score = |root: &str| scorer.score_candidate(root.to_string()).score;
+        let roots = root_forms(&self.source);
+
+        let stage4 = roots
+            .iter()
+            .filter(|root| check_fuhivla(root).is_ok())
+            .map(|root| FuhivlaCandidate {
+                word: root.clone(),
+                stage: FuhivlaStage::Four,
+                score: score(root),
+            });
+
+        let rafsi = self.category.as_ref().map(|gismu| &gismu[..4]);
+        let stage3 = rafsi.into_iter().flat_map(|rafsi| {
+            roots.iter().filter_map(move |root| {
+                STAGE3_HYPHENS
+                    .iter()
+                    .map(|&hyphen| format!("{}{}{}", rafsi, hyphen, root))
+                    .find(|word| check_stage3(word, rafsi.len()).is_ok())
+                    .map(|word| FuhivlaCandidate {
+                        word,
+                        stage: FuhivlaStage::Three,
+                        score: score(root),
+                    })
+            })
+        });
+
+        let mut candidates: Vec<FuhivlaCandidate> = stage4.chain(stage3).collect();
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.word.len().cmp(&b.word.len()))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        candidates
+    }
+}
+
+/// Check a stage-4 fu'ivla: phonotactics, vowel ending, an early consonant
+/// pair, and the tests keeping it from being read as a gismu, a lujvo or
+/// several words
+pub fn check_fuhivla(word: &str) -> Result<(), FuhivlaError> {
+    check_word_class(word, WordClass::Fuhivla)?;
+    check_brivla_shape(word)
+}
+
+/// Stage of a valid fu'ivla: stage 3 when it starts with the four-letter rafsi
+/// of a gismu and an `r`, `n` or `l` hyphen, stage 4 otherwise
+pub fn fuhivla_stage(word: &str) -> Result<FuhivlaStage, FuhivlaError> {
+    let rafsi = word.get(..4).unwrap_or_default();
+    let is_stage3 = rafsi_shape(rafsi).is_ok_and(|shape| shape.is_four_letter())
+        && word[4..].starts_with(STAGE3_HYPHENS)
+        && search_selrafsi_from_rafsi2(rafsi, false).is_some()
+        && check_stage3(word, rafsi.len()).is_ok();
+    if is_stage3 {
+        return Ok(FuhivlaStage::Three);
+    }
+    check_fuhivla(word).map(|_| FuhivlaStage::Four)
+}
+
+/// Stage-3 fu'ivla: the cluster joining rafsi, hyphen and root only needs
+/// permissible pairs, the rest of the word follows the stage-4 rules
+fn check_stage3(word: &str, rafsi_len: usize) -> Result<(), FuhivlaError> {
+    let chars: Vec<char> = word.chars().collect();
+    check_word_class(&word[rafsi_len + 1..], WordClass::Fuhivla)?;
+
+    let start = chars[..rafsi_len].iter().rposition(|&c| !is_consonant(c)).map_or(0, |i| i + 1);
+    let end = chars[rafsi_len..]
+        .iter()
+        .position(|&c| !is_consonant(c))
+        .map_or(chars.len(), |i| rafsi_len + i);
+    let junction = &chars[start..end];
+    if let Some(pair) = junction.windows(2).find(|p| !is_permissible_pair(p[0], p[1])) {
+        return Err(PhonotacticError::ForbiddenPair(pair.iter().collect()).into());
+    }
+    if let Some(triple) = junction
+        .windows(3)
+        .find(|t| is_forbidden_triple(t[0], t[1], t[2]))
+    {
+        return Err(PhonotacticError::ForbiddenTriple(triple.iter().collect()).into());
+    }
+    check_brivla_shape(word)
+}
+
+fn check_brivla_shape(word: &str) -> Result<(), FuhivlaError> {
+    if !word.ends_with(is_vowel) {
+        return Err(FuhivlaError::ConsonantEnding);
+    }
+    let letters: Vec<char> = word.chars().filter(|&c| c != '\'' && c != 'y').collect();
+    let early_cluster = letters
+        .windows(2)
+        .take(4)
+        .any(|pair| is_consonant(pair[0]) && is_consonant(pair[1]));
+    if !early_cluster {
+        return Err(FuhivlaError::NoEarlyCluster);
+    }
+    if syllables(&word.chars().collect::<Vec<_>>()) < 2 {
+        return Err(FuhivlaError::TooShort);
+    }
+    if rafsi_shape(word).is_ok_and(|shape| shape.is_gismu()) {
+        return Err(FuhivlaError::GismuShape);
+    }
+    if is_lujvo_shape(word) {
+        return Err(FuhivlaError::LujvoShape);
+    }
+    if is_lujvo_shape(&format!("pa{}", word)) {
+        return Err(FuhivlaError::Slinkuhi);
+    }
+    if falls_apart(word) {
+        return Err(FuhivlaError::Tosmabru);
+    }
+    Ok(())
+}
+
+/// True when the word splits into two or more rafsi forms, with `y`, `r` and
+/// `n` hyphens where lujvo use them
+pub fn is_lujvo_shape(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    splits_into_rafsi(&chars, 0)
+}
+
+fn splits_into_rafsi(chars: &[char], parts: usize) -> bool {
+    let pattern = cv_pattern_of(chars);
+    // Rafsi starting with two consonants start with an initial pair
+    if pattern.starts_with("CC") && !is_valid_initial_pair(chars[0], chars[1]) {
+        return false;
+    }
+    if parts > 0 && is_final_rafsi(chars, &pattern) {
+        return true;
+    }
+
+    let mut rests: Vec<&[char]> = Vec::new();
+    let starts = |p: &str| pattern.starts_with(p);
+    if (starts("CVCCY") || starts("CCVCY")) && chars.len() > 5 {
+        rests.push(&chars[5..]);
+    }
+    if starts("CVC") || starts("CCV") {
+        rests.push(&chars[3..]);
+        if pattern[3..].starts_with('Y') {
+            rests.push(&chars[4..]);
+        }
+    }
+    let cvv_len = if starts("CVV") && is_rafsi_vowel_pair(chars[1], chars[2]) {
+        Some(3)
+    } else if starts("CV'V") {
+        Some(4)
+    } else {
+        None
+    };
+    if let Some(len) = cvv_len {
+        rests.push(&chars[len..]);
+        if matches!(chars.get(len), Some('r' | 'n')) && chars.get(len + 1).is_some_and(|&c| is_consonant(c)) {
+            rests.push(&chars[len + 1..]);
+        }
+    }
+
+    rests
+        .into_iter()
+        .any(|rest| !rest.is_empty() && splits_into_rafsi(rest, parts + 1))
+}
+
+fn is_final_rafsi(chars: &[char], pattern: &str) -> bool {
+    match pattern {
+        "CVCCV" | "CCVCV" | "CCV" | "CV'V" => true,
+        "CVV" => is_rafsi_vowel_pair(chars[1], chars[2]),
+        _ => false,
+    }
+}
+
+fn is_rafsi_vowel_pair(v1: char, v2: char) -> bool {
+    is_diphthong(v1, v2)
+}
+
+/// True when leading CV... cmavo can be split off, leaving a brivla that starts
+/// with an initial cluster, as `tosmabru` reads `to smabru`
+fn falls_apart(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let mut i = 0;
+    while i < chars.len() && is_consonant(chars[i]) {
+        i += 1;
+        let vowels = chars[i..]
+            .iter()
+            .take_while(|&&c| is_vowel(c) || c == '\'')
+            .count();
+        if vowels == 0 || i + vowels >= chars.len() {
+            return false;
+        }
+        i += vowels;
+        let rest = &chars[i..];
+        if rest.len() >= 2
+            && is_consonant(rest[1])
+            && is_brivla_start(rest)
+            && check_word(&rest.iter().collect::<String>()).is_ok()
+            && syllables(rest) >= 2
+            && rest.last().is_some_and(|&c| is_vowel(c))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Leading consonant cluster made of initial pairs only
+fn is_brivla_start(chars: &[char]) -> bool {
+    let cluster: Vec<char> = chars.iter().take_while(|&&c| is_consonant(c)).copied().collect();
+    cluster.len() >= 2 && cluster.windows(2).all(|p| is_valid_initial_pair(p[0], p[1]))
+}
+
+fn syllables(chars: &[char]) -> usize {
+    (0..chars.len())
+        .filter(|&i| is_vowel(chars[i]) && !chars.get(i + 1).is_some_and(|&c| is_vowel(c)))
+        .count()
+}
+
+/// Root variants to try: the source with a vowel ending, with a consonant
+/// inserted to get an early cluster, and with impermissible clusters repaired.
+/// Repairs that leave fewer than two syllables of a longer source are
+/// dropped, so `koka` never shrinks to `ka`
+fn root_forms(source: &str) -> BTreeSet<String> {
+    let mut forms = BTreeSet::new();
+    if source.is_empty() {
+        return forms;
+    }
+    if source.ends_with(is_vowel) {
+        forms.insert(source.to_string());
+    } else {
+        forms.extend(V.chars().map(|v| format!("{}{}", source, v)));
+    }
+    let syllables_of = |form: &str| syllables(&form.chars().collect::<Vec<_>>());
+    let min_syllables = forms.iter().map(|form| syllables_of(form)).min().unwrap_or(0).min(2);
+
+    let clustered: Vec<String> = forms.iter().flat_map(|form| early_cluster_forms(form)).collect();
+    forms.extend(clustered);
+
+    let repaired: Vec<String> = forms
+        .iter()
+        .filter(|form| check_word(form).is_err())
+        .flat_map(|form| repaired_forms(form))
+        .collect();
+    forms.extend(repaired);
+
+    forms.retain(|form| syllables_of(form) >= min_syllables);
+    forms
+}
+
+/// Insert `r`, `n` or `l` after a vowel, or drop a vowel between two
+/// consonants, within the first five letters
+fn early_cluster_forms(form: &str) -> Vec<String> {
+    let chars: Vec<char> = form.chars().collect();
+    let mut forms = Vec::new();
+    for i in 0..chars.len().min(4) {
+        if is_vowel(chars[i]) && chars.get(i + 1).is_some_and(|&c| is_consonant(c)) {
+            for hyphen in ['r', 'n', 'l'] {
+                let mut inserted = chars.clone();
+                inserted.insert(i + 1, hyphen);
+                forms.push(inserted.into_iter().collect());
+            }
+        }
+        if i > 0
+            && is_vowel(chars[i])
+            && is_consonant(chars[i - 1])
+            && chars.get(i + 1).is_some_and(|&c| is_consonant(c))
+            && chars[i + 2..].iter().any(|&c| is_vowel(c))
+        {
+            let mut dropped = chars.clone();
+            dropped.remove(i);
+            forms.push(dropped.into_iter().collect());
+        }
+    }
+    forms
+}
+
+/// Break each impermissible consonant pair by dropping one consonant or by
+/// inserting the nearest preceding vowel, and each impermissible vowel pair
+/// by dropping one vowel or inserting an apostrophe
+fn repaired_forms(form: &str) -> Vec<String> {
+    let chars: Vec<char> = form.chars().collect();
+    let mut forms = Vec::new();
+    for i in 0..chars.len().saturating_sub(1) {
+        let (a, b) = (chars[i], chars[i + 1]);
+        if is_vowel(a) && is_vowel(b) && !is_valid_vowel_pair(a, b) {
+            for drop in [i, i + 1] {
+                let mut dropped = chars.clone();
+                dropped.remove(drop);
+                forms.push(dropped.into_iter().collect());
+            }
+            let mut inserted = chars.clone();
+            inserted.insert(i + 1, '\'');
+            forms.push(inserted.into_iter().collect());
+            continue;
+        }
+        if !is_consonant(a) || !is_consonant(b) {
+            continue;
+        }
+        // Word-initial pairs and pairs after another consonant must be initials
+        let needs_initial = i == 0 || is_consonant(chars[i - 1]);
+        if is_permissible_pair(a, b) && (!needs_initial || is_valid_initial_pair(a, b)) {
+            continue;
+        }
+        for drop in [i, i + 1] {
+            let mut dropped = chars.clone();
+            dropped.remove(drop);
+            forms.push(dropped.into_iter().collect());
+        }
+        let vowel = chars[..i].iter().rev().find(|&&c| is_vowel(c)).copied().unwrap_or('i');
+        let mut inserted = chars.clone();
+        inserted.insert(i + 1, vowel);
+        forms.push(inserted.into_iter().collect());
+    }
+    forms
+}
+
+fn cv_pattern_of(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|&c| match c {
+            c if is_consonant(c) => 'C',
+            c if is_vowel(c) => 'V',
+            'y' => 'Y',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_stage4_fuhivla() {
+        assert_eq!(check_fuhivla("spageti"), Ok(()));
+        assert_eq!(check_fuhivla("alga"), Ok(()));
+        assert_eq!(check_fuhivla("spagetin"), Err(FuhivlaError::ConsonantEnding));
+        assert_eq!(check_fuhivla("kobra"), Err(FuhivlaError::GismuShape));
+        assert_eq!(check_fuhivla("kalgau"), Err(FuhivlaError::LujvoShape));
+        assert!(matches!(check_fuhivla("mlaia"), Err(FuhivlaError::Phonotactics(_))));
+    }
+
+    #[test]
+    fn rejects_slinkuhi_failures() {
+        assert_eq!(check_fuhivla("slinku'i"), Err(FuhivlaError::Slinkuhi));
+    }
+
+    #[test]
+    fn rejects_tosmabru_failures() {
+        assert_eq!(check_fuhivla("tosmabrue"), Err(FuhivlaError::Tosmabru));
+        assert_eq!(check_fuhivla("lospageti"), Err(FuhivlaError::Tosmabru));
+        assert_eq!(check_fuhivla("lobrinei"), Err(FuhivlaError::Tosmabru));
+    }
+
+    #[test]
+    fn tells_stages_apart() {
+        assert_eq!(fuhivla_stage("spatrkoka"), Ok(FuhivlaStage::Three));
+        assert_eq!(fuhivla_stage("spageti"), Ok(FuhivlaStage::Four));
+    }
+
+    #[test]
+    fn keeps_roots_of_two_syllables() {
+        let generator = FuhivlaGenerator::new("koka").with_category("spati").unwrap();
+        let candidates = generator.candidates();
+        assert_eq!(candidates[0].word, "spatrkoka");
+        assert!(candidates.iter().all(|c| c.word != "spatrka"));
+
+        let short = FuhivlaGenerator::new("ka").with_category("spati").unwrap().candidates();
+        assert_eq!(short[0].word, "spatrka");
+        assert_eq!(
+            FuhivlaGenerator::new("ka").with_category("kla").err(),
+            Some(FuhivlaError::InvalidCategory("kla".to_string()))
+        );
+    }
+}
@@ -1,59 +1,273 @@
-use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use smallvec::SmallVec;
-use std::collections::HashSet;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::libs::config::{C, SIBILANT, SONORANT, UNVOICED, V, VOICED};
+use crate::libs::similarity_table::SimilarityTable;
+use crate::phonotactics::{check_word_class, is_consonant, is_valid_initial_pair, is_vowel, WordClass};
+use crate::similarity::{lcs_alignment, LcsDyadMetric, SimilarityMetric, SourceWord};
+
+pub use crate::similarity::{DyadKind, DyadMatch};
+
+/// Named letter classes usable in shapes as `{name}`
+static LETTER_CLASSES: [(&str, &str); 6] = [
+    ("sibilant", SIBILANT),
+    ("voiced", VOICED),
+    ("unvoiced", UNVOICED),
+    ("sonorant", SONORANT),
+    ("consonant", C),
+    ("vowel", V),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeError {
+    UnknownClass(String),
+    Unterminated(char),
+    EmptySet,
+    UnexpectedChar(char),
+    /// Shape whose candidates cannot be counted in a `usize`
+    TooManyCandidates(String),
+}
 
-use crate::libs::config::{
-    FORBIDDEN_CC, FORBIDDEN_CCC, SIBILANT, SIMILARITIES, UNVOICED, VALID_CC_INITIALS, VOICED,
-};
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShapeError::UnknownClass(name) => write!(
+                f,
+                "unknown letter class {{{}}}; known classes are {}",
+                name,
+                LETTER_CLASSES.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ")
+            ),
+            ShapeError::Unterminated(open) => write!(f, "'{}' is never closed", open),
+            ShapeError::EmptySet => write!(f, "letter set [] is empty"),
+            ShapeError::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            ShapeError::TooManyCandidates(shape) => write!(f, "shape '{}' has too many candidates", shape),
+        }
+    }
+}
 
-static VALID_CC_INITIALS_SET: Lazy<HashSet<&'static str>> =
-    Lazy::new(|| VALID_CC_INITIALS.iter().cloned().collect());
+impl Error for ShapeError {}
+
+/// One position of a shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShapeSlot {
+    /// `c`: any consonant from the letter pool
+    Consonant,
+    /// `v`: any vowel from the letter pool
+    Vowel,
+    /// `{name}`: letters of a named class that are in the letter pool
+    Class(&'static str),
+    /// Fixed letter, `'`, `y` or `[...]` set; not limited by the letter pool
+    Letters(Vec<char>),
+}
 
-static FORBIDDEN_CC_SET: Lazy<HashSet<&'static str>> =
-    Lazy::new(|| FORBIDDEN_CC.iter().cloned().collect());
+/// Word shape such as `ccvcv`, `cv'v`, `k{sibilant}vcv` or `[bp]vccv`
+///
+/// `c` and `v` stand for a consonant and a vowel, other letters as well as
+/// `'` and `y` stand for themselves, `[...]` lists allowed letters and
+/// `{sibilant}`, `{voiced}`, `{unvoiced}`, `{sonorant}`, `{consonant}` and
+/// `{vowel}` name letter classes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape {
+    slots: Vec<ShapeSlot>,
+}
 
-static FORBIDDEN_CCC_SET: Lazy<HashSet<&'static str>> =
-    Lazy::new(|| FORBIDDEN_CCC.iter().cloned().collect());
+impl Shape {
+    pub fn slots(&self) -> &[ShapeSlot] {
+        &self.slots
+    }
 
-static SIBILANT_SET: Lazy<HashSet<char>> = Lazy::new(|| SIBILANT.chars().collect());
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
 
-static VOICED_SET: Lazy<HashSet<char>> = Lazy::new(|| VOICED.chars().collect());
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
 
-static UNVOICED_SET: Lazy<HashSet<char>> = Lazy::new(|| UNVOICED.chars().collect());
+    /// True when every letter of `word` fits the slot at its position,
+    /// regardless of the letter pool
+    pub fn matches(&self, word: &str) -> bool {
+        word.chars().count() == self.slots.len()
+            && word.chars().zip(&self.slots).all(|(c, slot)| match slot {
+                ShapeSlot::Consonant => is_consonant(c),
+                ShapeSlot::Vowel => is_vowel(c),
+                ShapeSlot::Class(letters) => letters.contains(c),
+                ShapeSlot::Letters(letters) => letters.contains(&c),
+            })
+    }
 
-fn lcs_length(a: &str, b: &str) -> f32 {
-    let (a_bytes, b_bytes) = (a.as_bytes(), b.as_bytes());
-    let (m, n) = (a_bytes.len(), b_bytes.len());
-    
-    // Ensure a is the shorter string to optimize space usage
-    if m > n {
-        return lcs_length(b, a);
+    /// Word class of every candidate of the shape, see [`pattern_word_class`];
+    /// `None` when a slot mixes consonants, vowels, `y` and `'`
+    pub fn word_class(&self) -> Option<WordClass> {
+        let pattern = self
+            .slots
+            .iter()
+            .map(|slot| match slot {
+                ShapeSlot::Consonant => Some('C'),
+                ShapeSlot::Vowel => Some('V'),
+                ShapeSlot::Class(letters) => common_kind(letters.chars()),
+                ShapeSlot::Letters(letters) => common_kind(letters.iter().copied()),
+            })
+            .collect::<Option<String>>()?;
+        Some(pattern_word_class(&pattern))
     }
-    
-    // Use a single vector, initialized with zeros
-    let mut current = vec![0; m + 1];
+}
 
-    for j in 1..=n {
-        let mut prev = 0;
-        for i in 1..=m {
-            let temp = current[i];
-            if a_bytes[i - 1] == b_bytes[j - 1] {
-                current[i] = prev + 1;
-            } else {
-                current[i] = current[i].max(current[i - 1]);
+impl FromStr for Shape {
+    type Err = ShapeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut slots = Vec::with_capacity(s.len());
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            let slot = match c {
+                'c' | 'C' => ShapeSlot::Consonant,
+                'v' | 'V' => ShapeSlot::Vowel,
+                '[' => {
+                    let rest = chars.as_str();
+                    let end = rest.find(']').ok_or(ShapeError::Unterminated('['))?;
+                    let set = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    if set.is_empty() {
+                        return Err(ShapeError::EmptySet);
+                    }
+                    let letters: Vec<char> = set.chars().map(|c| c.to_ascii_lowercase()).collect();
+                    if let Some(&bad) = letters.iter().find(|&&c| !is_shape_letter(c)) {
+                        return Err(ShapeError::UnexpectedChar(bad));
+                    }
+                    ShapeSlot::Letters(letters)
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or(ShapeError::Unterminated('{'))?;
+                    let name = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    LETTER_CLASSES
+                        .iter()
+                        .find(|(class, _)| class.eq_ignore_ascii_case(name))
+                        .map(|(_, letters)| ShapeSlot::Class(letters))
+                        .ok_or_else(|| ShapeError::UnknownClass(name.to_string()))?
+                }
+                c if is_shape_letter(c.to_ascii_lowercase()) => {
+                    ShapeSlot::Letters(vec![c.to_ascii_lowercase()])
+                }
+                c => return Err(ShapeError::UnexpectedChar(c)),
+            };
+            slots.push(slot);
+        }
+
+        Ok(Shape { slots })
+    }
+}
+
+#[inline]
+fn is_shape_letter(c: char) -> bool {
+    is_consonant(c) || is_vowel(c) || c == 'y' || c == '\''
+}
+
+/// `C` for a consonant, `V` for a vowel; `y` and `'` stay as they are
+#[inline]
+fn letter_kind(c: char) -> char {
+    match c {
+        c if is_consonant(c) => 'C',
+        c if is_vowel(c) => 'V',
+        other => other,
+    }
+}
+
+/// [`letter_kind`] shared by all the letters, if any
+fn common_kind(mut letters: impl Iterator<Item = char>) -> Option<char> {
+    let first = letters.next().map(letter_kind)?;
+    letters.all(|c| letter_kind(c) == first).then_some(first)
+}
+
+/// Word class of a pattern of `C`, `V`, `y` and `'`: a final consonant makes a
+/// cmevla, a consonant pair or `y` hyphen a brivla, CVV and CV'V a rafsi-shaped
+/// cmavo and anything else a cmavo or cmavo cluster
+pub fn pattern_word_class(pattern: &str) -> WordClass {
+    if pattern.ends_with('C') {
+        WordClass::Cmevla
+    } else if pattern.contains("CC") || pattern.contains("CyC") {
+        WordClass::Brivla
+    } else if matches!(pattern, "CVV" | "CV'V") {
+        WordClass::Rafsi
+    } else {
+        WordClass::Cmavo
+    }
+}
+
+/// Restrictions on which letters may appear where in generated candidates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LetterConstraints {
+    /// Letters the candidate must begin with
+    pub starts_with: Option<String>,
+    /// Letters that must each occur somewhere in the candidate
+    pub contains: Vec<char>,
+    /// Letters that must not occur anywhere
+    pub exclude: Vec<char>,
+    /// One character per position: a letter to fix it or `?` for any
+    pub pattern: Option<String>,
+}
+
+impl LetterConstraints {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Letter required at each position of a word of the given length; `None`
+    /// when the constraints cannot be met by a word of that length
+    fn fixed_letters(&self, len: usize) -> Option<Vec<Option<char>>> {
+        let mut fixed = vec![None; len];
+
+        if let Some(pattern) = &self.pattern {
+            if pattern.chars().count() != len {
+                return None;
+            }
+            for (slot, c) in fixed.iter_mut().zip(pattern.chars()) {
+                if c != '?' {
+                    *slot = Some(c.to_ascii_lowercase());
+                }
+            }
+        }
+
+        if let Some(prefix) = &self.starts_with {
+            if prefix.chars().count() > len {
+                return None;
+            }
+            for (slot, c) in fixed.iter_mut().zip(prefix.chars()) {
+                let c = c.to_ascii_lowercase();
+                match *slot {
+                    Some(existing) if existing != c => return None,
+                    _ => *slot = Some(c),
+                }
             }
-            prev = temp;
         }
+
+        Some(fixed)
     }
+}
 
-    current[m] as f32
+/// Letters to try at each position of one shape, the number of candidates
+/// they give and the word class the candidates are checked against
+struct ShapePlan {
+    choices: Vec<Vec<char>>,
+    count: usize,
+    class: Option<WordClass>,
 }
 
 pub struct GismuGenerator {
     c: Vec<String>,
     v: Vec<String>,
     shape_strings: Vec<String>,
+    constraints: LetterConstraints,
+    clash_filter: Option<Arc<GismuMatcher>>,
 }
 
 impl GismuGenerator {
@@ -62,173 +276,366 @@ impl GismuGenerator {
             c,
             v,
             shape_strings,
+            constraints: LetterConstraints::default(),
+            clash_filter: None,
         }
     }
 
-    pub fn iterator(&self) -> Vec<String> {
-        self.shape_strings
-            .par_iter()
-            .flat_map(|shape_string| self.shape_iterator(shape_string))
-            .collect()
-    }
-
-    fn shape_iterator(&self, shape_string: &str) -> Vec<String> {
-        let shape = self.shape_for_string(shape_string);
-        let validator = self.shape_validator(shape_string);
-    
-        (0..shape.iter().map(|v| v.len()).product::<usize>())
-            .into_par_iter()
-            .filter_map(move |index| {
-                let mut candidate = String::with_capacity(shape.len());
-                let mut remaining = index;
-                for choices in &shape {
-                    let choice_index = remaining % choices.len();
-                    remaining /= choices.len();
-                    candidate.push_str(&choices[choice_index]);
-                }
-                if validator(&candidate) {
-                    Some(candidate)
-                } else {
-                    None
-                }
-            })
-            .collect()
+    /// Skip candidates that clash with an existing gismu while enumerating
+    pub fn with_clash_filter(mut self, matcher: Arc<GismuMatcher>) -> Self {
+        self.clash_filter = Some(matcher);
+        self
     }
 
-    fn shape_for_string(&self, string: &str) -> Vec<&[String]> {
-        string
-            .chars()
-            .map(|c| match c.to_ascii_lowercase() {
-                'c' => &self.c[..],
-                'v' => &self.v[..],
-                _ => &[],
-            })
-            .collect()
+    /// Limit enumeration to candidates meeting the constraints; fixed and
+    /// required letters are used even when they are not in the letter pool
+    pub fn with_constraints(mut self, constraints: LetterConstraints) -> Self {
+        self.constraints = constraints;
+        self
     }
 
-    fn shape_validator(&self, shape: &str) -> impl Fn(&str) -> bool {
-        type Predicate = Box<dyn Fn(&str) -> bool + Send + Sync>;
+    pub fn iterator(&self) -> Result<Vec<String>, ShapeError> {
+        Ok(self.par_candidates()?.collect())
+    }
 
-        let predicates: Vec<Predicate> = shape
-            .chars()
-            .zip(shape.chars().skip(1))
-            .enumerate()
-            .filter_map(|(i, (c1, c2))| {
-                if c1.to_ascii_lowercase() == 'c' && c2.to_ascii_lowercase() == 'c' {
-                    let mut p: Vec<Predicate> = vec![Box::new(self.validator_for_cc(i))];
-                    if shape.chars().nth(i + 2) == Some('c') {
-                        p.push(Box::new(self.validator_for_ccc(i)));
-                    }
-                    if i > 0 && shape[i..].starts_with("ccvcv") {
-                        p.push(Box::new(self.invalidator_for_initial_cc(i)));
-                    }
-                    Some(p)
-                } else {
-                    None
-                }
-            })
-            .flatten()
-            .collect();
+    /// Stream candidates of every shape without collecting them; fails on
+    /// the first shape that does not parse or has too many candidates
+    pub fn par_candidates(&self) -> Result<impl ParallelIterator<Item = String> + '_, ShapeError> {
+        let plans = self
+            .shape_strings
+            .iter()
+            .map(|shape_string| self.shape_plan(shape_string))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(plans.into_par_iter().flat_map(move |plan| self.shape_candidates(plan)))
+    }
 
-        move |x: &str| predicates.iter().all(|p| p(x))
+    fn shape_plan(&self, shape_string: &str) -> Result<ShapePlan, ShapeError> {
+        let shape: Shape = shape_string.parse()?;
+        let choices = self.choices_for_shape(&shape).unwrap_or_default();
+        let count = if choices.is_empty() {
+            0
+        } else {
+            choices
+                .iter()
+                .try_fold(1usize, |count, letters| count.checked_mul(letters.len()))
+                .ok_or_else(|| ShapeError::TooManyCandidates(shape_string.to_string()))?
+        };
+        Ok(ShapePlan {
+            choices,
+            count,
+            class: shape.word_class(),
+        })
     }
 
-    fn validator_for_cc(&self, i: usize) -> impl Fn(&str) -> bool {
-        move |x: &str| {
-            if i == 0 {
-                VALID_CC_INITIALS_SET.contains(&x[..2])
+    fn shape_candidates(&self, plan: ShapePlan) -> impl ParallelIterator<Item = String> + '_ {
+        let ShapePlan { choices, count, class } = plan;
+        let required = &self.constraints.contains;
+        let clash_filter = self.clash_filter.as_deref();
+
+        (0..count).into_par_iter().filter_map(move |index| {
+            let mut candidate = String::with_capacity(choices.len());
+            let mut remaining = index;
+            for letters in &choices {
+                let choice_index = remaining % letters.len();
+                remaining /= letters.len();
+                candidate.push(letters[choice_index]);
+            }
+            if required.iter().all(|&c| candidate.contains(c))
+                && is_valid_candidate(&candidate, class)
+                && !clash_filter.is_some_and(|matcher| matcher.clashes(&candidate))
+            {
+                Some(candidate)
             } else {
-                let j = i + 1;
-                let c1 = x.chars().nth(i).unwrap();
-                let c2 = x.chars().nth(j).unwrap();
-
-                !(c1 == c2
-                    || (VOICED_SET.contains(&c1) && UNVOICED_SET.contains(&c2))
-                    || (UNVOICED_SET.contains(&c1) && VOICED_SET.contains(&c2))
-                    || (SIBILANT_SET.contains(&c1) && SIBILANT_SET.contains(&c2))
-                    || FORBIDDEN_CC_SET.contains(&x[i..=j]))
+                None
             }
+        })
+    }
+
+    /// Letters to try at each position of the shape, or `None` when the
+    /// constraints rule the whole shape out
+    fn choices_for_shape(&self, shape: &Shape) -> Option<Vec<Vec<char>>> {
+        let constraints = &self.constraints;
+        let fixed = constraints.fixed_letters(shape.len())?;
+        let pool: Vec<char> = self
+            .c
+            .iter()
+            .chain(self.v.iter())
+            .flat_map(|s| s.chars())
+            .chain(constraints.contains.iter().copied())
+            .collect();
+
+        let choices: Vec<Vec<char>> = shape
+            .slots()
+            .iter()
+            .zip(fixed)
+            .map(|(slot, fixed)| {
+                let allowed = |c: char| match slot {
+                    ShapeSlot::Consonant => is_consonant(c),
+                    ShapeSlot::Vowel => is_vowel(c),
+                    ShapeSlot::Class(letters) => letters.contains(c),
+                    ShapeSlot::Letters(letters) => letters.contains(&c),
+                };
+                let letters: Vec<char> = match (fixed, slot) {
+                    (Some(c), _) => vec![c],
+                    (None, ShapeSlot::Letters(letters)) => letters.clone(),
+                    (None, _) => pool.clone(),
+                };
+                letters
+                    .into_iter()
+                    .filter(|&c| allowed(c) && !constraints.exclude.contains(&c))
+                    .fold(Vec::new(), |mut acc, c| {
+                        if !acc.contains(&c) {
+                            acc.push(c);
+                        }
+                        acc
+                    })
+            })
+            .collect();
+
+        let placeable = constraints
+            .contains
+            .iter()
+            .all(|c| choices.iter().any(|letters| letters.contains(c)));
+        if choices.iter().any(|letters| letters.is_empty()) || !placeable {
+            None
+        } else {
+            Some(choices)
         }
     }
+}
 
-    fn validator_for_ccc(&self, i: usize) -> impl Fn(&str) -> bool {
-        move |x| !FORBIDDEN_CCC_SET.contains(&x[i..=i + 2])
+/// Consonants and vowels of the letter pool that occur in the source words
+pub fn letters_for_words(words: &[String]) -> (Vec<String>, Vec<String>) {
+    let word_set: HashSet<char> = words.iter().flat_map(|word| word.chars()).collect();
+
+    (
+        C.chars()
+            .filter(|&c| word_set.contains(&c))
+            .map(|s| s.to_string())
+            .collect(),
+        V.chars()
+            .filter(|&c| word_set.contains(&c))
+            .map(|s| s.to_string())
+            .collect(),
+    )
+}
+
+/// Candidates must obey the phonotactics of their word class, taken from the
+/// shape or else from the candidate's own letters, and no CCVCV sequence
+/// inside them may start with a valid initial pair, or it could be mistaken
+/// for a gismu
+fn is_valid_candidate(candidate: &str, class: Option<WordClass>) -> bool {
+    let chars: Vec<char> = candidate.chars().collect();
+    let pattern: String = chars.iter().map(|&c| letter_kind(c)).collect();
+    let class = class.unwrap_or_else(|| pattern_word_class(&pattern));
+    if check_word_class(candidate, class).is_err() {
+        return false;
+    }
+
+    !(1..chars.len().saturating_sub(4))
+        .any(|i| pattern[i..].starts_with("CCVCV") && is_valid_initial_pair(chars[i], chars[i + 1]))
+}
+
+#[derive(Debug, Clone)]
+pub struct WordExplanation {
+    pub word: String,
+    /// Byte positions of the letters of the LCS in the candidate and in the
+    /// source word; empty for other metrics, which do not score an alignment
+    pub alignment: Vec<(usize, usize)>,
+    /// Set only when the LCS is two letters long and the dyad rule applied
+    pub dyad: Option<DyadMatch>,
+    /// Score of the LCS metric before dividing by the word length; the
+    /// normalized score for other metrics
+    pub raw_score: f32,
+    /// Score of the metric in use
+    pub normalized_score: f32,
+    pub weight: f32,
+    pub contribution: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreExplanation {
+    pub candidate: String,
+    pub score: f32,
+    /// Name of the similarity metric that produced the scores
+    pub metric: &'static str,
+    pub words: Vec<WordExplanation>,
+}
+
+/// Per-language similarity scores; stays on the stack for up to 12 languages
+pub type LanguageScores = SmallVec<[f32; 12]>;
+
+#[derive(Debug, Clone)]
+pub struct ScoredCandidate {
+    pub score: f32,
+    pub candidate: String,
+    pub similarities: LanguageScores,
+}
+
+/// Better candidates compare greater: higher score first, then alphabetical order
+impl Ord for ScoredCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.candidate.cmp(&self.candidate))
+    }
+}
+
+impl PartialOrd for ScoredCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
+
+impl PartialEq for ScoredCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScoredCandidate {}
 
-    fn invalidator_for_initial_cc(&self, i: usize) -> impl Fn(&str) -> bool {
-        move |x| !VALID_CC_INITIALS_SET.contains(&x[i..=i + 1])
+/// Min-heap of at most `k` elements: the worst one is dropped on overflow
+fn push_bounded(heap: &mut BinaryHeap<Reverse<ScoredCandidate>>, item: Reverse<ScoredCandidate>, k: usize) {
+    if heap.len() < k {
+        heap.push(item);
+    } else if heap.peek().is_some_and(|worst| item < *worst) {
+        heap.pop();
+        heap.push(item);
     }
 }
 
 pub struct GismuScorer<'a> {
     input_words: &'a [String],
-    weights: SmallVec<[f32; 6]>,
+    sources: Vec<SourceWord>,
+    weights: Vec<f32>,
+    metric: Box<dyn SimilarityMetric>,
 }
 
 impl<'a> GismuScorer<'a> {
     pub fn new(input_words: &'a [String], weights: &[f32]) -> Self {
         Self {
             input_words,
-            weights: SmallVec::from_slice(weights),
+            sources: input_words.iter().map(|word| SourceWord::new(word)).collect(),
+            weights: weights.to_vec(),
+            metric: Box::new(LcsDyadMetric),
         }
     }
 
-    fn compute_score(&self, candidate: &str) -> (f32, SmallVec<[f32; 6]>) {
-        let similarity_scores: SmallVec<[f32; 6]> = self
-            .input_words
+    pub fn with_metric(mut self, metric: Box<dyn SimilarityMetric>) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn metric_name(&self) -> &'static str {
+        self.metric.name()
+    }
+
+    pub fn language_count(&self) -> usize {
+        self.input_words.len()
+    }
+
+    fn compute_score(&self, candidate: &str) -> (f32, LanguageScores) {
+        let similarity_scores: LanguageScores = self
+            .sources
             .iter()
-            .map(|word| {
-                let lcs_len = lcs_length(candidate, word);
-                let score = match lcs_len {
-                    0.0 | 1.0 => 0.0,
-                    2.0 => self.score_dyad_by_pattern(candidate, word),
-                    _ => lcs_len,
-                };
-                score / word.len() as f32
-            })
+            .map(|source| self.metric.similarity(candidate, source))
             .collect();
 
         let weighted_sum = self.calculate_weighted_sum(&similarity_scores);
         (weighted_sum, similarity_scores)
     }
 
+    pub fn score_candidate(&self, candidate: String) -> ScoredCandidate {
+        let (score, similarities) = self.compute_score(&candidate);
+        ScoredCandidate {
+            score,
+            candidate,
+            similarities,
+        }
+    }
+
+    /// Score a stream of candidates in parallel, keeping only the `k` best
+    ///
+    /// # Returns
+    /// Number of candidates scored and the best ones, best first; equal scores
+    /// are ordered alphabetically
+    pub fn top_k<I>(&self, candidates: I, k: usize) -> (usize, Vec<ScoredCandidate>)
+    where
+        I: ParallelIterator<Item = String>,
+    {
+        let (count, heap) = candidates
+            .fold(
+                || (0usize, BinaryHeap::with_capacity(k + 1)),
+                |(count, mut heap), candidate| {
+                    push_bounded(&mut heap, Reverse(self.score_candidate(candidate)), k);
+                    (count + 1, heap)
+                },
+            )
+            .reduce(
+                || (0usize, BinaryHeap::new()),
+                |(count_a, mut heap_a), (count_b, heap_b)| {
+                    for scored in heap_b {
+                        push_bounded(&mut heap_a, scored, k);
+                    }
+                    (count_a + count_b, heap_a)
+                },
+            );
+
+        (
+            count,
+            heap.into_sorted_vec().into_iter().map(|Reverse(scored)| scored).collect(),
+        )
+    }
+
     pub fn compute_score_with_name<'b>(
         &self,
         candidate: &'b String,
-    ) -> (f32, &'b String, SmallVec<[f32; 6]>) {
+    ) -> (f32, &'b String, LanguageScores) {
         let (weighted_sum, similarity_scores) = self.compute_score(candidate);
         (weighted_sum, candidate, similarity_scores)
     }
 
-    fn score_dyad_by_pattern(&self, candidate: &str, input_word: &str) -> f32 {
-        let l = candidate.len();
-        let iw02: String = input_word.chars().step_by(2).collect();
-        let iw12: String = input_word.chars().skip(1).step_by(2).collect();
-
-        let mut score = 0.0;
-
-        for i in 0..(l - 2) {
-            let dyad = &candidate[i..(i + 2)];
-            if iw02.contains(dyad) || iw12.contains(dyad) {
-                score = 2.0;
-                break;
-            }
-        }
-
-        if score == 0.0 {
-            for i in 0..(l - 1) {
-                let dyad = &candidate[i..(i + 2)];
-                if input_word.contains(dyad) {
-                    score = 2.0;
-                    break;
+    /// Break down the score of a candidate word by word
+    pub fn explain(&self, candidate: &str) -> ScoreExplanation {
+        let words: Vec<WordExplanation> = self
+            .input_words
+            .iter()
+            .zip(&self.sources)
+            .zip(self.weights.iter())
+            .map(|((word, source), &weight)| {
+                let normalized_score = self.metric.similarity(candidate, source);
+                let (alignment, dyad, raw_score) = if self.metric.name() == LcsDyadMetric.name() {
+                    let alignment = lcs_alignment(candidate, word);
+                    let dyad = match alignment.len() {
+                        2 => source.find_dyad(candidate),
+                        _ => None,
+                    };
+                    (alignment, dyad, LcsDyadMetric.raw_score(candidate, source))
+                } else {
+                    (Vec::new(), None, normalized_score)
+                };
+                WordExplanation {
+                    word: word.clone(),
+                    alignment,
+                    dyad,
+                    raw_score,
+                    normalized_score,
+                    weight,
+                    contribution: normalized_score * weight,
                 }
-            }
-        }
+            })
+            .collect();
 
-        score
+        ScoreExplanation {
+            candidate: candidate.to_string(),
+            score: words.iter().map(|w| w.contribution).sum(),
+            metric: self.metric.name(),
+            words,
+        }
     }
 
-    fn calculate_weighted_sum(&self, scores: &SmallVec<[f32; 6]>) -> f32 {
+    fn calculate_weighted_sum(&self, scores: &[f32]) -> f32 {
         scores
             .iter()
             .zip(self.weights.iter())
@@ -237,55 +644,195 @@ impl<'a> GismuScorer<'a> {
     }
 }
 
-pub struct GismuMatcher<'a> {
-    gismus: &'a [String],
+/// Matches candidates against existing gismu through precomputed indexes:
+/// a candidate clashes with a gismu that starts with its stem, or that differs
+/// from it in a single letter listed as similar in its similarity table
+pub struct GismuMatcher {
+    gismus: Vec<String>,
     stem_length: usize,
+    similarities: SimilarityTable,
+    /// First index of a gismu per stem
+    stems: HashMap<Vec<u8>, usize>,
+    /// Gismu with one position replaced by `*`, with the replaced letter and
+    /// the gismu index
+    wildcards: HashMap<Vec<u8>, Vec<(u8, usize)>>,
+    /// Same keys over the prefixes of longer gismu, for shorter candidates
+    prefix_wildcards: HashMap<Vec<u8>, Vec<(u8, usize)>>,
+    /// Lengths of the gismu in the list, for candidates longer than some gismu
+    lengths: Vec<usize>,
 }
 
-impl<'a> GismuMatcher<'a> {
-    pub fn new(gismus: &'a [String], stem_length: Option<usize>) -> Self {
+impl GismuMatcher {
+    pub fn new(gismus: &[String], stem_length: Option<usize>) -> Self {
+        let stem_length = stem_length.unwrap_or(4);
+        let mut stems = HashMap::new();
+        let mut wildcards: HashMap<Vec<u8>, Vec<(u8, usize)>> = HashMap::new();
+        let mut prefix_wildcards: HashMap<Vec<u8>, Vec<(u8, usize)>> = HashMap::new();
+        let mut lengths = BTreeSet::new();
+
+        for (index, gismu) in gismus.iter().enumerate() {
+            let bytes = gismu.as_bytes();
+            lengths.insert(bytes.len());
+            if bytes.len() >= stem_length {
+                stems.entry(bytes[..stem_length].to_vec()).or_insert(index);
+            }
+            for len in 1..=bytes.len() {
+                let map = if len == bytes.len() {
+                    &mut wildcards
+                } else {
+                    &mut prefix_wildcards
+                };
+                for i in 0..len {
+                    map.entry(wildcard_key(&bytes[..len], i))
+                        .or_default()
+                        .push((bytes[i], index));
+                }
+            }
+        }
+
         Self {
-            gismus,
-            stem_length: stem_length.unwrap_or(4),
+            gismus: gismus.to_vec(),
+            stem_length,
+            similarities: SimilarityTable::default(),
+            stems,
+            wildcards,
+            prefix_wildcards,
+            lengths: lengths.into_iter().collect(),
         }
     }
 
+    pub fn with_similarity_table(mut self, similarities: SimilarityTable) -> Self {
+        self.similarities = similarities;
+        self
+    }
+
+    pub fn similarity_table(&self) -> &SimilarityTable {
+        &self.similarities
+    }
+
+    /// First gismu of the list that the candidate clashes with
     pub fn find_similar_gismu(&self, candidate: &str) -> Option<String> {
-        let candidate = candidate.trim_end();
+        self.first_clash(candidate.trim_end())
+            .map(|index| self.gismus[index].clone())
+    }
 
-        self.gismus
+    pub fn clashes(&self, candidate: &str) -> bool {
+        self.first_clash(candidate.trim_end()).is_some()
+    }
+
+    fn first_clash(&self, candidate: &str) -> Option<usize> {
+        let bytes = candidate.as_bytes();
+        let stem = bytes
+            .get(..self.stem_length)
+            .and_then(|stem| self.stems.get(stem))
+            .copied();
+
+        // Gismu at least as long as the candidate are compared on the
+        // candidate's length, shorter ones on their own length
+        let same_or_longer = (0..bytes.len()).flat_map(|i| {
+            let key = wildcard_key(bytes, i);
+            let found = [self.wildcards.get(&key), self.prefix_wildcards.get(&key)];
+            found
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(move |&&(letter, _)| self.is_similar(bytes[i], letter))
+                .map(|&(_, index)| index)
+                .collect::<Vec<_>>()
+        });
+        let shorter = self
+            .lengths
             .iter()
-            .find(|word| self.match_gismu(word, candidate))
-            .cloned()
+            .take_while(|&&len| len < bytes.len())
+            .flat_map(|&len| {
+                (0..len).flat_map(move |i| {
+                    self.wildcards
+                        .get(&wildcard_key(&bytes[..len], i))
+                        .into_iter()
+                        .flatten()
+                        .filter(move |&&(letter, _)| self.is_similar(bytes[i], letter))
+                        .map(|&(_, index)| index)
+                })
+            });
+
+        stem.into_iter().chain(same_or_longer).chain(shorter).min()
     }
 
-    fn match_gismu(&self, gismu: &str, candidate: &str) -> bool {
-        self.match_stem(gismu, candidate) || self.match_structure(gismu, candidate)
+    fn is_similar(&self, candidate_letter: u8, gismu_letter: u8) -> bool {
+        self.similarities
+            .is_similar(candidate_letter as char, gismu_letter as char)
     }
+}
 
-    fn match_structure(&self, gismu: &str, candidate: &str) -> bool {
-        let common_len = candidate.len().min(gismu.len());
-        (0..common_len).any(|i| {
-            self.strings_match_except(gismu, candidate, i, common_len)
-                && self
-                    .match_structural_pattern(&gismu[i..i + 1], candidate.chars().nth(i).unwrap())
-        })
+fn wildcard_key(bytes: &[u8], position: usize) -> Vec<u8> {
+    let mut key = bytes.to_vec();
+    key[position] = b'*';
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(shape: &str) -> Result<Vec<String>, ShapeError> {
+        let letters = |s: &str| s.chars().map(|c| c.to_string()).collect();
+        GismuGenerator::new(letters(C), letters(V), vec![shape.to_string()]).iterator()
     }
 
-    fn match_structural_pattern(&self, letter: &str, c: char) -> bool {
-        SIMILARITIES
-            .iter()
-            .find(|&&(key, _)| key == c.to_ascii_lowercase())
-            .map_or(false, |&(_, pattern)| {
-                pattern.contains(letter) || pattern.is_empty()
-            })
+    fn class_of(shape: &str) -> Option<WordClass> {
+        shape.parse::<Shape>().unwrap().word_class()
+    }
+
+    #[test]
+    fn brivla_shapes() {
+        assert_eq!(class_of("ccvcv"), Some(WordClass::Brivla));
+        assert_eq!(class_of("cvcycvcv"), Some(WordClass::Brivla));
+        let candidates = generate("ccvcv").unwrap();
+        assert!(candidates.contains(&"klama".to_string()));
+        assert!(!candidates.contains(&"mzana".to_string()));
+    }
+
+    #[test]
+    fn cmavo_shapes() {
+        assert_eq!(class_of("cy"), Some(WordClass::Cmavo));
+        assert_eq!(class_of("cv"), Some(WordClass::Cmavo));
+        let candidates = generate("cy").unwrap();
+        assert_eq!(candidates.len(), C.len());
+        assert!(candidates.contains(&"by".to_string()));
+        assert!(generate("cv").unwrap().contains(&"lo".to_string()));
+    }
+
+    #[test]
+    fn rafsi_shapes() {
+        assert_eq!(class_of("cvv"), Some(WordClass::Rafsi));
+        assert_eq!(class_of("cv'v"), Some(WordClass::Rafsi));
+        let candidates = generate("cvv").unwrap();
+        assert!(candidates.contains(&"sai".to_string()));
+        assert!(!candidates.contains(&"dua".to_string()));
+        assert!(generate("cv'v").unwrap().contains(&"ga'u".to_string()));
+    }
+
+    #[test]
+    fn cmevla_shapes() {
+        assert_eq!(class_of("cvc"), Some(WordClass::Cmevla));
+        let candidates = generate("cvcc").unwrap();
+        assert!(candidates.contains(&"kant".to_string()));
+        assert!(!candidates.contains(&"kamz".to_string()));
     }
 
-    fn match_stem(&self, gismu: &str, candidate: &str) -> bool {
-        candidate.len() >= self.stem_length && gismu.starts_with(&candidate[..self.stem_length])
+    #[test]
+    fn mixed_slots_take_the_class_of_each_candidate() {
+        assert_eq!(class_of("c[ay]"), None);
+        let mut candidates = generate("b[ay]").unwrap();
+        candidates.sort();
+        assert_eq!(candidates, ["ba", "by"]);
     }
 
-    fn strings_match_except(&self, x: &str, y: &str, i: usize, j: usize) -> bool {
-        x[..i] == y[..i] && x[(i + 1)..j] == y[(i + 1)..j]
+    #[test]
+    fn reports_bad_shapes() {
+        assert_eq!(generate("cv{nasal}"), Err(ShapeError::UnknownClass("nasal".to_string())));
+        assert_eq!(generate("c[vv"), Err(ShapeError::Unterminated('[')));
+        let long = "cv".repeat(20);
+        assert_eq!(generate(&long), Err(ShapeError::TooManyCandidates(long.clone())));
     }
 }
@@ -1,4 +1,5 @@
-use crate::jvozba::scoring::get_cv_info;
+use crate::jvozba::rafsi_shape::{rafsi_shape, RafsiShape};
+use crate::phonotactics::{check_word_class, is_consonant, WordClass};
 use std::error::Error;
 use std::fmt;
 
@@ -25,7 +26,14 @@ impl Error for LujvoError {}
 /// # Returns
 /// Result with vector of rafsi or error message
 pub fn jvokaha(lujvo: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let invalid = |e| LujvoError {
+        message: format!("invalid lujvo {{{}}}: {}", lujvo, e),
+    };
+    check_word_class(lujvo, WordClass::Brivla).map_err(invalid)?;
     let arr = jvokaha2(lujvo)?;
+    for rafsi in arr.iter().filter(|a| a.len() != 1) {
+        check_word_class(rafsi, WordClass::Rafsi).map_err(invalid)?;
+    }
     let rafsi_list: Vec<String> = arr.iter().filter(|a| a.len() != 1).cloned().collect();
 
     let correct_lujvo = normalize(&rafsi_list).join("");
@@ -46,48 +54,43 @@ fn jvokaha2(lujvo: &str) -> Result<Vec<String>, Box<dyn Error>> {
     let original_lujvo = lujvo.to_string();
     let mut res: Vec<String> = Vec::new();
     let mut lujvo = lujvo.to_string();
+    // Shape of the first `n` letters, `None` when they are not a rafsi
+    let prefix_shape = |lujvo: &str, n: usize| lujvo.get(0..n).and_then(|prefix| rafsi_shape(prefix).ok());
 
     while !lujvo.is_empty() {
         // Remove hyphen
         if !res.is_empty() && res.last().unwrap().len() != 1 && (lujvo.starts_with('y')
-                || lujvo.starts_with("nr") || (lujvo.starts_with('r') && get_cv_info(&lujvo[1..2]) == "C")) {
+                || lujvo.starts_with("nr") || (lujvo.starts_with('r') && lujvo[1..].starts_with(is_consonant))) {
             res.push(lujvo[0..1].to_string());
             lujvo = lujvo[1..].to_string();
             continue;
         }
 
         // Drop rafsi from front
-        if get_cv_info(&lujvo[0..3]) == "CVV" && ["ai", "ei", "oi", "au"].contains(&&lujvo[1..3]) {
-            res.push(lujvo[0..3].to_string());
-            lujvo = lujvo[3..].to_string();
-            continue;
-        }
-
-        if get_cv_info(&lujvo[0..4]) == "CV'V" {
-            res.push(lujvo[0..4].to_string());
-            lujvo = lujvo[4..].to_string();
+        if let Some(len) = [3, 4].into_iter().find(|&n| prefix_shape(&lujvo, n).is_some_and(|shape| shape.is_cvv())) {
+            res.push(lujvo[0..len].to_string());
+            lujvo = lujvo[len..].to_string();
             continue;
         }
 
-        if get_cv_info(&lujvo[0..5]) == "CVCCY" || get_cv_info(&lujvo[0..5]) == "CCVCY" {
+        if prefix_shape(&lujvo, 4).is_some_and(|shape| shape.is_four_letter()) && lujvo[4..].starts_with('y') {
             res.push(lujvo[0..4].to_string());
             res.push("y".to_string());
             lujvo = lujvo[5..].to_string();
             continue;
         }
 
-        if get_cv_info(&lujvo) == "CVCCV" || get_cv_info(&lujvo) == "CCVCV" {
+        if rafsi_shape(&lujvo).is_ok_and(|shape| shape.is_gismu()) {
             res.push(lujvo);
             return Ok(res);
         }
 
-        if get_cv_info(&lujvo[0..3]) == "CVC" || get_cv_info(&lujvo[0..3]) == "CCV" {
+        if let Some(RafsiShape::Cvc | RafsiShape::Ccv) = prefix_shape(&lujvo, 3) {
             res.push(lujvo[0..3].to_string());
             lujvo = lujvo[3..].to_string();
             continue;
         }
 
-        println!("{:?}, {}", res, lujvo);
         return Err(Box::new(LujvoError {
             message: format!("Failed to decompose {{{}}}", original_lujvo),
         }));
@@ -1,30 +1,7 @@
+use super::rafsi_shape::{rafsi_shape, RafsiShape};
 use super::{scoring::get_lujvo_score, tools};
 use tools::{create_every_possibility, get_candid};
-use once_cell::sync::Lazy;
-use serde_json::Value;
-use std::collections::HashMap;
-
-static PERMISSIBILITY_TABLE: Lazy<HashMap<char, HashMap<char, i32>>> = Lazy::new(|| {
-    let json: Value = serde_json::from_str(include_str!("permissible.json"))
-        .expect("Invalid JSON in permissibility_table.json");
-    json.as_object()
-        .unwrap()
-        .iter()
-        .map(|(k, v)| {
-            (k.chars().next().unwrap(),
-             v.as_object()
-                .unwrap()
-                .iter()
-                .map(|(k2, v2)| (k2.chars().next().unwrap(), v2.as_i64().unwrap() as i32))
-                .collect())
-        })
-        .collect()
-});
-
-#[inline]
-fn is_permissible(c1: char, c2: char) -> i32 {
-    PERMISSIBILITY_TABLE.get(&c1).and_then(|row| row.get(&c2)).copied().unwrap_or(0)
-}
+use crate::phonotactics::{is_valid_triple, pair_kind, PairKind};
 
 #[derive(Debug, Clone)]
 pub struct LujvoAndScore {
@@ -64,21 +41,44 @@ pub fn jvozba(arr: &[String], forbid_la_lai_doi: bool, exp_rafsi: bool) -> Vec<L
 
 #[inline]
 fn is_forbidden(d: &LujvoAndScore, forbid_la_lai_doi: bool) -> bool {
-    let l = &d.lujvo;
-    is_cmevla(l)
-        && forbid_la_lai_doi
-        && (l.starts_with("lai")
-            || l.starts_with("doi")
-            || l.contains("lai")
-            || l.contains("doi")
-            || (l.starts_with("la") && !l.starts_with("lau"))
-            || l.split(&['a', 'e', 'i', 'o', 'u', 'y'][..])
-                .any(|m| m.starts_with("la") && !m.starts_with("lau")))
+    is_cmevla(&d.lujvo) && forbid_la_lai_doi && has_la_lai_doi(&d.lujvo, LaLaiDoiRule::Anywhere)
+}
+
+/// Which `la`, `lai` and `doi` sequences split a cmevla apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaLaiDoiRule {
+    /// `lai` and `doi` anywhere and a leading `la` (but not `lau`), as
+    /// jvozba has always filtered lujvo
+    Anywhere,
+    /// `la` (but not `lau`), `lai` and `doi` not preceded by a consonant
+    AfterVowel,
+}
+
+/// True when the word contains a `la`, `lai` or `doi` forbidden by `rule`
+pub fn has_la_lai_doi(word: &str, rule: LaLaiDoiRule) -> bool {
+    la_lai_doi_positions(word, rule).next().is_some()
+}
+
+/// Byte offsets of the `la`, `lai` and `doi` sequences found by [`has_la_lai_doi`]
+pub fn la_lai_doi_positions(word: &str, rule: LaLaiDoiRule) -> impl Iterator<Item = usize> + '_ {
+    let bytes = word.as_bytes();
+    (0..bytes.len()).filter(move |&i| {
+        let rest = &bytes[i..];
+        let lai_doi = rest.starts_with(b"lai") || rest.starts_with(b"doi");
+        let la = rest.starts_with(b"la") && !rest.starts_with(b"lau");
+        match rule {
+            LaLaiDoiRule::Anywhere => lai_doi || (i == 0 && la),
+            LaLaiDoiRule::AfterVowel => {
+                let after_consonant = i > 0 && !"aeiouy',".contains(bytes[i - 1] as char);
+                (lai_doi || la) && !after_consonant
+            }
+        }
+    })
 }
 
 #[inline]
 fn is_cmevla(valsi: &str) -> bool {
-    valsi.chars().last().map_or(false, |c| !"aeiouy'".contains(c))
+    valsi.chars().last().is_some_and(|c| !"aeiouy'".contains(c))
 }
 
 pub fn normalize(rafsi_list: &[String]) -> Vec<String> {
@@ -92,8 +92,8 @@ pub fn normalize(rafsi_list: &[String]) -> Vec<String> {
         let init = result[0].chars().next().unwrap();
 
         if is_4letter(rafsi) 
-            || (is_c(end) && is_c(init) && is_permissible(end, init) == 0)
-            || (end == 'n' && ["ts", "tc", "dz", "dj"].iter().any(|&s| result[0].starts_with(s)))
+            || (is_c(end) && is_c(init) && pair_kind(end, init) == PairKind::Forbidden)
+            || starts_invalid_triple(end, &result[0])
             || (i == rafsi_list.len() - 2 && is_cvv(rafsi) && should_add_hyphen(rafsi_list, &result))
             || (i == rafsi_list.len() - 2 && is_cvc(rafsi) && is_tosmabru(rafsi, &result))
         {
@@ -111,6 +111,14 @@ pub fn normalize(rafsi_list: &[String]) -> Vec<String> {
     result
 }
 
+/// Joining the consonant `end` to a rafsi starting with two consonants
+/// would not give a valid triple, e.g. `ndj`
+fn starts_invalid_triple(end: char, rafsi: &str) -> bool {
+    let mut chars = rafsi.chars();
+    matches!((chars.next(), chars.next()), (Some(a), Some(b))
+        if is_c(end) && is_c(a) && is_c(b) && !is_valid_triple(end, a, b))
+}
+
 #[inline]
 fn should_add_hyphen(rafsi_list: &[String], result: &[String]) -> bool {
     rafsi_list.len() > 2 || !is_ccv(&result[0])
@@ -127,8 +135,8 @@ fn is_tosmabru(rafsi: &str, rest: &[String]) -> bool {
 
     if index < rest.len() {
         let s = &rest[index];
-        if s != "y" && (get_cv_info(s) != "CVCCV" 
-            || is_permissible(s.chars().nth(2).unwrap(), s.chars().nth(3).unwrap()) != 2) {
+        if s != "y" && (rafsi_shape(s) != Ok(RafsiShape::Cvccv)
+            || pair_kind(s.chars().nth(2).unwrap(), s.chars().nth(3).unwrap()) != PairKind::Initial) {
             return false;
         }
     }
@@ -142,7 +150,7 @@ fn is_tosmabru(rafsi: &str, rest: &[String]) -> bool {
         let a = tmp1.chars().last().unwrap();
         let b = tmp2.chars().next().unwrap();
 
-        if is_permissible(a, b) != 2 {
+        if pair_kind(a, b) != PairKind::Initial {
             return false;
         }
 
@@ -154,38 +162,25 @@ fn is_tosmabru(rafsi: &str, rest: &[String]) -> bool {
 
 #[inline]
 fn is_cvv(rafsi: &str) -> bool {
-    matches!(get_cv_info(rafsi).as_str(), "CVV" | "CV'V")
+    rafsi_shape(rafsi).is_ok_and(|shape| shape.is_cvv())
 }
 
 #[inline]
 fn is_ccv(rafsi: &str) -> bool {
-    get_cv_info(rafsi) == "CCV"
+    rafsi_shape(rafsi) == Ok(RafsiShape::Ccv)
 }
 
 #[inline]
 fn is_cvc(rafsi: &str) -> bool {
-    get_cv_info(rafsi) == "CVC"
+    rafsi_shape(rafsi) == Ok(RafsiShape::Cvc)
 }
 
 #[inline]
 fn is_4letter(rafsi: &str) -> bool {
-    matches!(get_cv_info(rafsi).as_str(), "CVCC" | "CCVC")
+    rafsi_shape(rafsi).is_ok_and(|shape| shape.is_four_letter())
 }
 
 #[inline]
 fn is_c(c: char) -> bool {
     "bcdfgjklmnprstvxz".contains(c)
 }
-
-fn get_cv_info(v: &str) -> String {
-    v.chars()
-        .map(|c| match c {
-            'a' | 'e' | 'i' | 'o' | 'u' => 'V',
-            'b' | 'c' | 'd' | 'f' | 'g' | 'j' | 'k' | 'l' | 'm' | 'n' | 'p' | 'r' | 's' | 't'
-            | 'v' | 'x' | 'z' => 'C',
-            '\'' => '\'',
-            'y' => 'Y',
-            _ => panic!("Unexpected character: {}", c),
-        })
-        .collect()
-}
@@ -2,5 +2,7 @@ pub mod jvozbanarge;
 pub use jvozbanarge::*;
 pub mod tools;
 pub mod scoring;
+pub mod rafsi_shape;
 pub mod rafsi_list;
-pub mod jvokaha;
\ No newline at end of file
+pub mod jvokaha;
+pub mod rafsi_space;
@@ -0,0 +1,154 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::phonotactics::{is_consonant, is_diphthong, is_vowel};
+
+/// Shape of a rafsi, including a full gismu used as the last rafsi of a lujvo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RafsiShape {
+    Cvccv,
+    Cvcc,
+    Ccvcv,
+    Ccvc,
+    Cvc,
+    /// CV'V, e.g. `ga'u`
+    Cvhv,
+    Ccv,
+    /// CVV with a diphthong, e.g. `sai`
+    Cvv,
+}
+
+impl RafsiShape {
+    /// Letter pattern, e.g. `CV'V`
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            RafsiShape::Cvccv => "CVCCV",
+            RafsiShape::Cvcc => "CVCC",
+            RafsiShape::Ccvcv => "CCVCV",
+            RafsiShape::Ccvc => "CCVC",
+            RafsiShape::Cvc => "CVC",
+            RafsiShape::Cvhv => "CV'V",
+            RafsiShape::Ccv => "CCV",
+            RafsiShape::Cvv => "CVV",
+        }
+    }
+
+    /// Rafsi term of the lujvo score, from 1 for CVCCV to 8 for CVV
+    pub fn rank(&self) -> i32 {
+        match self {
+            RafsiShape::Cvccv => 1,
+            RafsiShape::Cvcc => 2,
+            RafsiShape::Ccvcv => 3,
+            RafsiShape::Ccvc => 4,
+            RafsiShape::Cvc => 5,
+            RafsiShape::Cvhv => 6,
+            RafsiShape::Ccv => 7,
+            RafsiShape::Cvv => 8,
+        }
+    }
+
+    /// Full gismu, only allowed at the end of a lujvo
+    pub fn is_gismu(&self) -> bool {
+        matches!(self, RafsiShape::Cvccv | RafsiShape::Ccvcv)
+    }
+
+    /// Four-letter rafsi, which needs a `y` hyphen before the next rafsi
+    pub fn is_four_letter(&self) -> bool {
+        matches!(self, RafsiShape::Cvcc | RafsiShape::Ccvc)
+    }
+
+    /// CVV or CV'V
+    pub fn is_cvv(&self) -> bool {
+        matches!(self, RafsiShape::Cvv | RafsiShape::Cvhv)
+    }
+}
+
+impl fmt::Display for RafsiShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pattern())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RafsiShapeError {
+    InvalidLetter(char),
+    /// Letters do not form a rafsi shape, e.g. `CVCV`
+    UnknownShape(String),
+    /// CVV rafsi whose vowels are not a diphthong, e.g. `xia`
+    NotDiphthong(String),
+}
+
+impl fmt::Display for RafsiShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RafsiShapeError::InvalidLetter(c) => write!(f, "'{}' is not a Lojban letter", c),
+            RafsiShapeError::UnknownShape(rafsi) => write!(f, "'{}' does not have a rafsi shape", rafsi),
+            RafsiShapeError::NotDiphthong(rafsi) => {
+                write!(f, "the vowels of '{}' are not a diphthong", rafsi)
+            }
+        }
+    }
+}
+
+impl Error for RafsiShapeError {}
+
+/// Classify a rafsi by its consonants, vowels and apostrophe
+pub fn rafsi_shape(rafsi: &str) -> Result<RafsiShape, RafsiShapeError> {
+    let chars: Vec<char> = rafsi.chars().collect();
+    let mut pattern = String::with_capacity(chars.len());
+    for &c in &chars {
+        pattern.push(match c {
+            c if is_consonant(c) => 'C',
+            c if is_vowel(c) => 'V',
+            '\'' => '\'',
+            other => return Err(RafsiShapeError::InvalidLetter(other)),
+        });
+    }
+
+    match pattern.as_str() {
+        "CVCCV" => Ok(RafsiShape::Cvccv),
+        "CVCC" => Ok(RafsiShape::Cvcc),
+        "CCVCV" => Ok(RafsiShape::Ccvcv),
+        "CCVC" => Ok(RafsiShape::Ccvc),
+        "CVC" => Ok(RafsiShape::Cvc),
+        "CV'V" => Ok(RafsiShape::Cvhv),
+        "CCV" => Ok(RafsiShape::Ccv),
+        "CVV" if is_diphthong(chars[1], chars[2]) => Ok(RafsiShape::Cvv),
+        "CVV" => Err(RafsiShapeError::NotDiphthong(rafsi.to_string())),
+        _ => Err(RafsiShapeError::UnknownShape(rafsi.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_every_shape() {
+        let shapes = [
+            ("klama", RafsiShape::Ccvcv),
+            ("gasnu", RafsiShape::Cvccv),
+            ("kalg", RafsiShape::Cvcc),
+            ("klam", RafsiShape::Ccvc),
+            ("kal", RafsiShape::Cvc),
+            ("ga'u", RafsiShape::Cvhv),
+            ("kla", RafsiShape::Ccv),
+            ("sai", RafsiShape::Cvv),
+        ];
+        for (rafsi, shape) in shapes {
+            assert_eq!(rafsi_shape(rafsi), Ok(shape), "{}", rafsi);
+            assert_eq!(shape.pattern().len(), rafsi.len());
+        }
+        assert_eq!(RafsiShape::Cvv.rank(), 8);
+        assert_eq!(RafsiShape::Cvhv.to_string(), "CV'V");
+    }
+
+    #[test]
+    fn rejects_non_rafsi() {
+        assert_eq!(rafsi_shape("xia"), Err(RafsiShapeError::NotDiphthong("xia".to_string())));
+        assert_eq!(rafsi_shape("kala"), Err(RafsiShapeError::UnknownShape("kala".to_string())));
+        assert_eq!(rafsi_shape(""), Err(RafsiShapeError::UnknownShape(String::new())));
+        assert_eq!(rafsi_shape("kly"), Err(RafsiShapeError::InvalidLetter('y')));
+        assert_eq!(rafsi_shape("kaw"), Err(RafsiShapeError::InvalidLetter('w')));
+    }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use super::rafsi_list::{
+    get_cmavo_rafsi_list, get_cmavo_rafsi_list_exp, get_gismu_rafsi_list, get_gismu_rafsi_list_exp,
+};
+use crate::libs::config::{C, DIPHTHONGS, V};
+use crate::phonotactics::valid_initial_pairs;
+
+/// Rafsi shapes covered by the report, in the order they are listed
+pub const RAFSI_SPACE_SHAPES: [&str; 4] = ["CVC", "CCV", "CVV", "CV'V"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RafsiStatus {
+    Official,
+    Experimental,
+    Free,
+}
+
+/// Which embedded rafsi list an assignment comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RafsiSource {
+    Gismu,
+    GismuExperimental,
+    Cmavo,
+    CmavoExperimental,
+}
+
+impl RafsiSource {
+    pub fn is_official(self) -> bool {
+        matches!(self, RafsiSource::Gismu | RafsiSource::Cmavo)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RafsiOwner {
+    pub selrafsi: String,
+    pub source: RafsiSource,
+}
+
+/// A single form of the rafsi space together with the words it is assigned to
+#[derive(Debug, Clone, Serialize)]
+pub struct RafsiSlot {
+    pub rafsi: String,
+    pub shape: &'static str,
+    pub status: RafsiStatus,
+    pub owners: Vec<RafsiOwner>,
+}
+
+impl RafsiSlot {
+    /// True when the rafsi is assigned to more than one distinct word
+    pub fn is_duplicate(&self) -> bool {
+        let selrafsi: HashSet<&str> = self.owners.iter().map(|o| o.selrafsi.as_str()).collect();
+        selrafsi.len() > 1
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShapeSummary {
+    pub shape: &'static str,
+    pub total: usize,
+    pub official: usize,
+    pub experimental: usize,
+    pub free: usize,
+    pub duplicates: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RafsiSpaceReport {
+    pub summary: Vec<ShapeSummary>,
+    pub duplicates: Vec<RafsiSlot>,
+    /// Listed rafsi that do not fit any of the enumerated short rafsi forms
+    pub unlisted: Vec<RafsiSlot>,
+    pub slots: Vec<RafsiSlot>,
+}
+
+/// Enumerate every CVC, CCV, CVV and CV'V form and mark how it is used
+/// by the embedded rafsi lists
+///
+/// # Returns
+/// Report with per-shape counts, duplicated assignments and every slot
+pub fn rafsi_space_report() -> RafsiSpaceReport {
+    let mut owners = collect_owners();
+
+    let slots: Vec<RafsiSlot> = RAFSI_SPACE_SHAPES
+        .iter()
+        .flat_map(|&shape| enumerate_shape(shape).into_iter().map(move |rafsi| (shape, rafsi)))
+        .map(|(shape, rafsi)| {
+            let owners = owners.remove(&rafsi).unwrap_or_default();
+            RafsiSlot {
+                status: status_for(&owners),
+                rafsi,
+                shape,
+                owners,
+            }
+        })
+        .collect();
+
+    let unlisted: Vec<RafsiSlot> = owners
+        .into_iter()
+        .map(|(rafsi, owners)| RafsiSlot {
+            status: status_for(&owners),
+            rafsi,
+            shape: "other",
+            owners,
+        })
+        .collect();
+
+    let summary = RAFSI_SPACE_SHAPES
+        .iter()
+        .map(|&shape| {
+            slots
+                .iter()
+                .filter(|slot| slot.shape == shape)
+                .fold(ShapeSummary { shape, ..Default::default() }, |mut acc, slot| {
+                    acc.total += 1;
+                    match slot.status {
+                        RafsiStatus::Official => acc.official += 1,
+                        RafsiStatus::Experimental => acc.experimental += 1,
+                        RafsiStatus::Free => acc.free += 1,
+                    }
+                    if slot.is_duplicate() {
+                        acc.duplicates += 1;
+                    }
+                    acc
+                })
+        })
+        .collect();
+
+    let duplicates = slots
+        .iter()
+        .chain(unlisted.iter())
+        .filter(|slot| slot.is_duplicate())
+        .cloned()
+        .collect();
+
+    RafsiSpaceReport {
+        summary,
+        duplicates,
+        unlisted,
+        slots,
+    }
+}
+
+fn collect_owners() -> BTreeMap<String, Vec<RafsiOwner>> {
+    let lists = [
+        (get_gismu_rafsi_list(), RafsiSource::Gismu),
+        (get_gismu_rafsi_list_exp(), RafsiSource::GismuExperimental),
+        (get_cmavo_rafsi_list(), RafsiSource::Cmavo),
+        (get_cmavo_rafsi_list_exp(), RafsiSource::CmavoExperimental),
+    ];
+
+    let mut owners: BTreeMap<String, Vec<RafsiOwner>> = BTreeMap::new();
+    for (list, source) in lists {
+        for (selrafsi, rafsi_list) in list {
+            for rafsi in rafsi_list {
+                owners.entry(rafsi.clone()).or_default().push(RafsiOwner {
+                    selrafsi: selrafsi.clone(),
+                    source,
+                });
+            }
+        }
+    }
+    for list in owners.values_mut() {
+        list.sort_by(|a, b| a.source.cmp(&b.source).then_with(|| a.selrafsi.cmp(&b.selrafsi)));
+    }
+    owners
+}
+
+fn status_for(owners: &[RafsiOwner]) -> RafsiStatus {
+    if owners.iter().any(|o| o.source.is_official()) {
+        RafsiStatus::Official
+    } else if owners.is_empty() {
+        RafsiStatus::Free
+    } else {
+        RafsiStatus::Experimental
+    }
+}
+
+fn enumerate_shape(shape: &str) -> Vec<String> {
+    let consonants = || C.chars();
+    let vowels = || V.chars();
+
+    match shape {
+        "CVC" => consonants()
+            .flat_map(|c1| vowels().flat_map(move |v| consonants().map(move |c2| format!("{c1}{v}{c2}"))))
+            .collect(),
+        "CCV" => valid_initial_pairs()
+            .flat_map(|cc| vowels().map(move |v| format!("{cc}{v}")))
+            .collect(),
+        "CVV" => consonants()
+            .flat_map(|c| DIPHTHONGS.iter().map(move |vv| format!("{c}{vv}")))
+            .collect(),
+        "CV'V" => consonants()
+            .flat_map(|c| vowels().flat_map(move |v1| vowels().map(move |v2| format!("{c}{v1}'{v2}"))))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
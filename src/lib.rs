@@ -18,13 +18,21 @@ assert_eq!(decomposition, vec!["kal", "ga'u"]);
 ```
 */
 
+pub mod cmevla;
+pub mod fuhivla;
 pub mod gismu_utils;
 pub mod jvozba;
 pub mod libs;
+pub mod morphology;
+pub mod phonotactics;
+pub mod pronunciation;
+pub mod similarity;
+pub mod transliteration;
 
 pub use jvozba::{
     jvokaha,
     jvozba,
+    rafsi_shape::{rafsi_shape, RafsiShape},
     scoring::get_lujvo_score,
     tools::{get_candid, search_selrafsi_from_rafsi2},
 };
@@ -0,0 +1,203 @@
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::gismu_utils::{letters_for_words, GismuGenerator, GismuScorer, ScoredCandidate};
+use crate::libs::config::{C, V};
+use crate::similarity::metric_from_name;
+
+/// A historical gismu with the source words it was made from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestEntry {
+    pub gismu: String,
+    pub words: Vec<String>,
+}
+
+/// How a historical gismu fares against the candidates generated from its
+/// source words
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestResult {
+    pub gismu: String,
+    pub words: Vec<String>,
+    /// Position of the gismu among the candidates, 1 being the winner; counted
+    /// even when the gismu itself is not generated
+    pub rank: usize,
+    /// False when the gismu uses letters or a shape the generator does not offer
+    pub generated: bool,
+    pub candidates: usize,
+    pub gismu_score: f32,
+    pub winner: Option<String>,
+    pub winner_score: Option<f32>,
+}
+
+impl BacktestResult {
+    /// True when the algorithm's winner is not the historical gismu
+    pub fn differs(&self) -> bool {
+        self.winner.as_deref() != Some(self.gismu.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BacktestSummary {
+    pub entries: usize,
+    pub generated: usize,
+    /// Entries whose gismu was generated and ranked first
+    pub top1: usize,
+    /// Entries whose gismu was generated and ranked in the first ten
+    pub top10: usize,
+    pub mean_rank: f64,
+    pub median_rank: f64,
+}
+
+/// Settings shared by every entry of a backtest run
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub weights: Vec<f32>,
+    pub shapes: Vec<String>,
+    pub metric: String,
+    pub all_letters: bool,
+}
+
+/// Read a CSV of `gismu,word1,...,wordN` rows; a header row starting with
+/// `gismu` and `#` comments are skipped
+pub fn load_backtest_table(path: &Path, language_count: usize) -> anyhow::Result<Vec<BacktestEntry>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read backtest table {}: {}", path.display(), e))?;
+    parse_backtest_table(&content, language_count)
+}
+
+pub fn parse_backtest_table(content: &str, language_count: usize) -> anyhow::Result<Vec<BacktestEntry>> {
+    let mut entries = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<String> = line.split(',').map(|f| f.trim().to_lowercase()).collect();
+        if entries.is_empty() && fields[0] == "gismu" {
+            continue;
+        }
+        if fields.len() != language_count + 1 {
+            anyhow::bail!(
+                "Line {}: expected a gismu and {} source words, got {} fields",
+                line_number + 1,
+                language_count,
+                fields.len()
+            );
+        }
+        if fields[1..].iter().any(|word| word.len() < 2) {
+            anyhow::bail!("Line {}: source words must be at least two letters long", line_number + 1);
+        }
+        entries.push(BacktestEntry {
+            gismu: fields[0].clone(),
+            words: fields[1..].to_vec(),
+        });
+    }
+    if entries.is_empty() {
+        anyhow::bail!("Backtest table has no entries");
+    }
+    Ok(entries)
+}
+
+/// Generate and score candidates for one entry and locate the historical gismu
+pub fn backtest_entry(entry: &BacktestEntry, config: &BacktestConfig) -> anyhow::Result<BacktestResult> {
+    let (c, v) = if config.all_letters {
+        (
+            C.chars().map(|s| s.to_string()).collect(),
+            V.chars().map(|s| s.to_string()).collect(),
+        )
+    } else {
+        letters_for_words(&entry.words)
+    };
+    let generator = GismuGenerator::new(c, v, config.shapes.clone());
+    let scorer = GismuScorer::new(&entry.words, &config.weights).with_metric(metric_from_name(&config.metric)?);
+    let target = scorer.score_candidate(entry.gismu.clone());
+
+    let (candidates, better, generated, winner) = generator
+        .par_candidates()?
+        .map(|candidate| scorer.score_candidate(candidate))
+        .fold(
+            || (0usize, 0usize, false, None::<ScoredCandidate>),
+            |(count, better, found, best), scored| {
+                (
+                    count + 1,
+                    better + usize::from(scored > target),
+                    found || scored.candidate == target.candidate,
+                    best.max(Some(scored)),
+                )
+            },
+        )
+        .reduce(
+            || (0, 0, false, None),
+            |a, b| (a.0 + b.0, a.1 + b.1, a.2 || b.2, a.3.max(b.3)),
+        );
+
+    Ok(BacktestResult {
+        gismu: entry.gismu.clone(),
+        words: entry.words.clone(),
+        rank: better + 1,
+        generated,
+        candidates,
+        gismu_score: target.score,
+        winner_score: winner.as_ref().map(|w| w.score),
+        winner: winner.map(|w| w.candidate),
+    })
+}
+
+pub fn run_backtest(entries: &[BacktestEntry], config: &BacktestConfig) -> anyhow::Result<Vec<BacktestResult>> {
+    entries.iter().map(|entry| backtest_entry(entry, config)).collect()
+}
+
+pub fn summarize(results: &[BacktestResult]) -> BacktestSummary {
+    if results.is_empty() {
+        return BacktestSummary::default();
+    }
+    let mut ranks: Vec<usize> = results.iter().map(|r| r.rank).collect();
+    ranks.sort_unstable();
+    // Both indexes are the middle one for an odd count
+    let median_rank = (ranks[(ranks.len() - 1) / 2] + ranks[ranks.len() / 2]) as f64 / 2.0;
+
+    BacktestSummary {
+        entries: results.len(),
+        generated: results.iter().filter(|r| r.generated).count(),
+        top1: results.iter().filter(|r| r.generated && r.rank == 1).count(),
+        top10: results.iter().filter(|r| r.generated && r.rank <= 10).count(),
+        mean_rank: ranks.iter().sum::<usize>() as f64 / ranks.len() as f64,
+        median_rank,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(gismu: &str, rank: usize, generated: bool, winner: &str) -> BacktestResult {
+        BacktestResult {
+            gismu: gismu.to_string(),
+            words: Vec::new(),
+            rank,
+            generated,
+            candidates: 100,
+            gismu_score: 0.5,
+            winner: Some(winner.to_string()),
+            winner_score: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn summarizes_ranks_of_generated_gismu() {
+        let results = [
+            result("blanu", 1, true, "blanu"),
+            result("cukta", 4, true, "cunta"),
+            result("xrula", 1, false, "xrala"),
+            result("kanpe", 20, true, "kanpa"),
+        ];
+        let summary = summarize(&results);
+        assert_eq!((summary.entries, summary.generated, summary.top1, summary.top10), (4, 3, 1, 2));
+        assert_eq!(summary.median_rank, 2.5);
+        assert_eq!(summary.mean_rank, 6.5);
+        assert!(results[2].differs());
+        assert_eq!(summarize(&results[..3]).median_rank, 1.0);
+    }
+}
@@ -1,16 +1,12 @@
-use regex::Regex;
-use once_cell::sync::Lazy;
-
-use super::config::language_weights;
-static WEIGHT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d{4}|finprims)$").unwrap());
+use super::config::PRESET_LANGUAGES;
+use super::profile::find_profile;
 
+/// Parse comma-separated weights, or take them from a built-in profile such as `1995`
 pub fn generate_weights(weights_str: &str) -> anyhow::Result<Vec<f32>> {
-    // Replace the existing Regex::new() call with the WEIGHT_REGEX static
-    if WEIGHT_REGEX.is_match(weights_str) {
-        language_weights()
-            .get(weights_str)
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("No weights registered for {}", weights_str))
+    if let Some(profile) = find_profile(weights_str.trim(), &[]) {
+        Ok(profile.weights())
+    } else if is_profile_name(weights_str.trim()) {
+        anyhow::bail!("No weights registered for {}", weights_str)
     } else {
         weights_str
             .split(',')
@@ -20,9 +16,77 @@ pub fn generate_weights(weights_str: &str) -> anyhow::Result<Vec<f32>> {
     }
 }
 
-pub fn validate_words(words: &[String], weights: &[f32]) -> anyhow::Result<()> {
-    if words.len() != weights.len() {
-        anyhow::bail!("Expected {} words as input", weights.len());
+/// Anything that is not a list of numbers, including bare years, names a profile
+fn is_profile_name(weights_str: &str) -> bool {
+    !weights_str.contains(',')
+        && (weights_str.chars().any(|c| c.is_ascii_alphabetic())
+            || (weights_str.len() == 4 && weights_str.chars().all(|c| c.is_ascii_digit())))
+}
+
+/// Check weights against their languages and normalize them to sum to 1
+///
+/// # Returns
+/// Normalized weights and warnings worth showing to the user
+pub fn normalize_weights(
+    weights: &[f32],
+    languages: &[String],
+) -> anyhow::Result<(Vec<f32>, Vec<String>)> {
+    if weights.is_empty() {
+        anyhow::bail!("At least one weight is required");
+    }
+    if weights.len() != languages.len() {
+        anyhow::bail!(
+            "Got {} weights for {} languages",
+            weights.len(),
+            languages.len()
+        );
+    }
+    if let Some((language, weight)) = languages
+        .iter()
+        .zip(weights)
+        .find(|(_, w)| !w.is_finite() || **w < 0.0)
+    {
+        anyhow::bail!("Weight {} for {} must not be negative", weight, language);
+    }
+
+    let sum: f32 = weights.iter().sum();
+    if sum == 0.0 {
+        anyhow::bail!("At least one weight must be greater than zero");
+    }
+
+    let mut warnings: Vec<String> = languages
+        .iter()
+        .zip(weights)
+        .filter(|(_, &w)| w == 0.0)
+        .map(|(language, _)| format!("{} has zero weight and does not affect scores", language))
+        .collect();
+
+    if (sum - 1.0).abs() > 1e-3 {
+        warnings.push(format!("Weights sum to {}; normalizing them to 1", sum));
+        Ok((weights.iter().map(|w| w / sum).collect(), warnings))
+    } else {
+        Ok((weights.to_vec(), warnings))
+    }
+}
+
+/// Default column names for weights given without a profile; six weights
+/// are taken to follow the order of the historical presets
+pub fn default_language_names(count: usize) -> Vec<String> {
+    if count == PRESET_LANGUAGES.len() {
+        PRESET_LANGUAGES.iter().map(|l| l.to_string()).collect()
+    } else {
+        (1..=count).map(|i| format!("lang{}", i)).collect()
+    }
+}
+
+pub fn validate_words(words: &[String], languages: &[String]) -> anyhow::Result<()> {
+    if words.len() != languages.len() {
+        anyhow::bail!(
+            "Expected {} words as input, one per language ({}), got {}",
+            languages.len(),
+            languages.join(", "),
+            words.len()
+        );
     }
     if words.iter().any(|word| word.len() < 2) {
         anyhow::bail!("Input words must be at least two letters long");
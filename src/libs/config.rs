@@ -13,17 +13,24 @@ pub static DEFAULT_WEIGHTS_STR: Lazy<String> = Lazy::new(|| {
         .join(",")
 });
 
+/// Source languages of the historical presets, in the order of their weights
+pub const PRESET_LANGUAGES: [&str; 6] = ["Chinese", "Hindi", "English", "Spanish", "Russian", "Arabic"];
+
+/// vlazba has no separate source for the `finprims` weights, so that profile
+/// reuses the 1987 weights
 pub fn language_weights() -> HashMap<&'static str, Vec<f32>> {
-    [
+    let mut weights: HashMap<&'static str, Vec<f32>> = [
         ("1985", vec![0.36, 0.16, 0.21, 0.11, 0.09, 0.07]),
         ("1987", vec![0.36, 0.156, 0.208, 0.116, 0.087, 0.073]),
         ("1994", vec![0.348, 0.194, 0.163, 0.123, 0.088, 0.084]),
         ("1995", vec![0.347, 0.196, 0.16, 0.123, 0.089, 0.085]),
         ("1999", vec![0.334, 0.195, 0.187, 0.116, 0.081, 0.088]),
     ]
-    .iter()
-    .cloned()
-    .collect()
+    .into_iter()
+    .collect();
+    let finprims = weights["1987"].clone();
+    weights.insert("finprims", finprims);
+    weights
 }
 
 pub const C: &str = "bcdfgjklmnprstvxz";
@@ -35,6 +42,8 @@ pub const VALID_CC_INITIALS: &[&str] = &[
     "sn", "sp", "sr", "st", "tc", "tr", "ts", "vl", "vr", "xl", "xr", "zb", "zd", "zg", "zm", "zv",
 ];
 
+pub const DIPHTHONGS: &[&str] = &["ai", "ei", "oi", "au"];
+
 pub const FORBIDDEN_CC: &[&str] = &["cx", "kx", "xc", "xk", "mz"];
 
 pub const FORBIDDEN_CCC: &[&str] = &["ndj", "ndz", "ntc", "nts"];
@@ -42,7 +51,7 @@ pub const FORBIDDEN_CCC: &[&str] = &["ndj", "ndz", "ntc", "nts"];
 pub const SIBILANT: &str = "cjsz";
 pub const VOICED: &str = "bdgjvz";
 pub const UNVOICED: &str = "cfkpstx";
-
+pub const SONORANT: &str = "lmnr";
 
 pub static SIMILARITIES: [(char, &str); 17] = [
     ('b', "pv"),
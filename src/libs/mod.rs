@@ -1,2 +1,15 @@
+pub mod backtest;
 pub mod cli;
 pub mod config;
+pub mod population;
+pub mod profile;
+pub mod similarity_table;
+
+/// Write a file into the temporary directory for a test; the name is
+/// prefixed with the process id so parallel test runs do not collide
+#[cfg(test)]
+pub(crate) fn write_temp(name: &str, content: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("vlazba-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::profile::{LanguageProfile, ProfileLanguage};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguagePopulation {
+    pub language: String,
+    pub l1: f64,
+    pub l2: f64,
+}
+
+/// How speaker numbers are turned into a weight before normalization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightFormula {
+    /// Native speakers only
+    L1,
+    /// Second-language speakers only
+    L2,
+    /// Native speakers plus a share of second-language speakers
+    Combined { l2_factor: f64 },
+}
+
+impl WeightFormula {
+    pub fn apply(&self, population: &LanguagePopulation) -> f64 {
+        match *self {
+            WeightFormula::L1 => population.l1,
+            WeightFormula::L2 => population.l2,
+            WeightFormula::Combined { l2_factor } => population.l1 + l2_factor * population.l2,
+        }
+    }
+}
+
+impl Default for WeightFormula {
+    fn default() -> Self {
+        WeightFormula::Combined { l2_factor: 1.0 }
+    }
+}
+
+impl fmt::Display for WeightFormula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeightFormula::L1 => write!(f, "l1"),
+            WeightFormula::L2 => write!(f, "l2"),
+            WeightFormula::Combined { l2_factor } if *l2_factor == 1.0 => write!(f, "l1+l2"),
+            WeightFormula::Combined { l2_factor } => write!(f, "l1+{}*l2", l2_factor),
+        }
+    }
+}
+
+impl FromStr for WeightFormula {
+    type Err = anyhow::Error;
+
+    /// Accepts `l1`, `l2`, `l1+l2` and `l1+K*l2`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let formula: String = s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+        match formula.as_str() {
+            "l1" => Ok(WeightFormula::L1),
+            "l2" => Ok(WeightFormula::L2),
+            "l1+l2" => Ok(WeightFormula::default()),
+            _ => formula
+                .strip_prefix("l1+")
+                .and_then(|rest| rest.strip_suffix("*l2"))
+                .and_then(|factor| factor.parse::<f64>().ok())
+                .filter(|factor| *factor >= 0.0)
+                .map(|l2_factor| WeightFormula::Combined { l2_factor })
+                .ok_or_else(|| anyhow::anyhow!("Unknown weight formula '{}'; use l1, l2, l1+l2 or l1+K*l2", s)),
+        }
+    }
+}
+
+/// Read a CSV of `language,l1[,l2]` rows; a header row and `#` comments are skipped
+pub fn load_population_table(path: &Path) -> anyhow::Result<Vec<LanguagePopulation>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read population table {}: {}", path.display(), e))?;
+    parse_population_table(&content)
+}
+
+pub fn parse_population_table(content: &str) -> anyhow::Result<Vec<LanguagePopulation>> {
+    let mut rows = Vec::new();
+    let mut first_line = true;
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let is_header_candidate = std::mem::replace(&mut first_line, false);
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parse = |i: usize| -> Option<Result<f64, _>> {
+            fields
+                .get(i)
+                .filter(|f| !f.is_empty())
+                .map(|f| f.replace('_', "").parse::<f64>())
+        };
+
+        match (parse(1), parse(2)) {
+            (Some(Err(_)), _) if is_header_candidate => continue,
+            (Some(Ok(l1)), l2) => {
+                let l2 = match l2 {
+                    Some(Ok(l2)) => l2,
+                    Some(Err(_)) => anyhow::bail!("Line {}: invalid L2 speaker count", line_number + 1),
+                    None => 0.0,
+                };
+                if l1 < 0.0 || l2 < 0.0 {
+                    anyhow::bail!("Line {}: speaker counts must not be negative", line_number + 1);
+                }
+                rows.push(LanguagePopulation {
+                    language: fields[0].to_string(),
+                    l1,
+                    l2,
+                });
+            }
+            _ => anyhow::bail!("Line {}: expected language,l1[,l2]", line_number + 1),
+        }
+    }
+
+    if rows.is_empty() {
+        anyhow::bail!("Population table has no languages");
+    }
+    Ok(rows)
+}
+
+/// Derive a profile whose weights are the normalized formula values
+pub fn derive_profile(
+    name: &str,
+    table: &[LanguagePopulation],
+    formula: WeightFormula,
+) -> anyhow::Result<LanguageProfile> {
+    let raw: Vec<f64> = table.iter().map(|row| formula.apply(row)).collect();
+    let total: f64 = raw.iter().sum();
+    if total <= 0.0 {
+        anyhow::bail!("Formula {} gives no speakers for any language", formula);
+    }
+
+    Ok(LanguageProfile {
+        name: name.to_string(),
+        description: Some(format!("derived with {}", formula)),
+        languages: table
+            .iter()
+            .zip(raw)
+            .map(|(row, value)| ProfileLanguage {
+                name: row.language.clone(),
+                weight: (value / total) as f32,
+                transliterator: None,
+            })
+            .collect(),
+    })
+}
+
+/// Derived weight of a language next to its weight in an existing profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightComparison {
+    pub language: String,
+    pub derived: f32,
+    pub reference: Option<f32>,
+}
+
+/// Match languages by name (case-insensitively) against a reference profile,
+/// e.g. one of the historical presets
+pub fn compare_weights(derived: &LanguageProfile, reference: &LanguageProfile) -> Vec<WeightComparison> {
+    derived
+        .languages
+        .iter()
+        .map(|language| WeightComparison {
+            language: language.name.clone(),
+            derived: language.weight,
+            reference: reference
+                .languages
+                .iter()
+                .find(|r| r.name.eq_ignore_ascii_case(&language.name))
+                .map(|r| r.weight),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::profile::find_profile;
+
+    #[test]
+    fn parses_formulas() {
+        assert_eq!("l1".parse::<WeightFormula>().unwrap(), WeightFormula::L1);
+        assert_eq!(" L1 + L2 ".parse::<WeightFormula>().unwrap(), WeightFormula::default());
+        assert_eq!(
+            "l1+0.5*l2".parse::<WeightFormula>().unwrap(),
+            WeightFormula::Combined { l2_factor: 0.5 }
+        );
+        assert_eq!(WeightFormula::Combined { l2_factor: 0.5 }.to_string(), "l1+0.5*l2");
+        for bad in ["l1+0.5l2", "l1+-1*l2", "l3", ""] {
+            assert!(bad.parse::<WeightFormula>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_tables_with_header_and_comments() {
+        let table = parse_population_table("# speakers\nlanguage,l1,l2\nA,1_000,500\nB, 250\n").unwrap();
+        assert_eq!(
+            table,
+            [
+                LanguagePopulation { language: "A".to_string(), l1: 1000.0, l2: 500.0 },
+                LanguagePopulation { language: "B".to_string(), l1: 250.0, l2: 0.0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        let error = |content: &str| parse_population_table(content).unwrap_err().to_string();
+        assert_eq!(error(""), "Population table has no languages");
+        assert_eq!(error("A,1\nB,x\n"), "Line 2: expected language,l1[,l2]");
+        assert_eq!(error("A,1,many\n"), "Line 1: invalid L2 speaker count");
+        assert_eq!(error("A,-1\n"), "Line 1: speaker counts must not be negative");
+    }
+
+    #[test]
+    fn derives_normalized_weights() {
+        let table = parse_population_table("Chinese,300,100\nKlingon,100,100\n").unwrap();
+        let derived = derive_profile("derived", &table, WeightFormula::Combined { l2_factor: 0.5 }).unwrap();
+        let weights: Vec<f32> = derived.languages.iter().map(|l| l.weight).collect();
+        assert_eq!(weights, [0.7, 0.3]);
+
+        let reference = find_profile("1995", &[]).unwrap();
+        let comparisons = compare_weights(&derived, &reference);
+        assert_eq!(comparisons[0].reference, Some(reference.languages[0].weight));
+        assert_eq!(comparisons[1].reference, None);
+
+        let nobody = parse_population_table("A,0,0\n").unwrap();
+        assert!(derive_profile("none", &nobody, WeightFormula::L1).is_err());
+    }
+}
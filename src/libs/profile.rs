@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::config::{language_weights, PRESET_LANGUAGES};
+use crate::transliteration::Transliterator;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileLanguage {
+    pub name: String,
+    pub weight: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transliterator: Option<String>,
+}
+
+/// Ordered source languages with their weights
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageProfile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(alias = "language")]
+    pub languages: Vec<ProfileLanguage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ProfileFile {
+    Many {
+        #[serde(alias = "profiles")]
+        profile: Vec<LanguageProfile>,
+    },
+    List(Vec<LanguageProfile>),
+    Single(LanguageProfile),
+}
+
+impl LanguageProfile {
+    pub fn weights(&self) -> Vec<f32> {
+        self.languages.iter().map(|l| l.weight).collect()
+    }
+
+    pub fn language_names(&self) -> Vec<String> {
+        self.languages.iter().map(|l| l.name.clone()).collect()
+    }
+
+    /// Transliterator for every language; languages without one take input as is
+    pub fn transliterators(&self) -> anyhow::Result<Vec<Transliterator>> {
+        self.languages
+            .iter()
+            .map(|l| match &l.transliterator {
+                Some(name) => name.parse(),
+                None => Ok(Transliterator::None),
+            })
+            .collect()
+    }
+
+    pub fn has_transliterators(&self) -> bool {
+        self.languages.iter().any(|l| l.transliterator.is_some())
+    }
+}
+
+/// Historical weight presets as profiles, ordered by name
+pub fn builtin_profiles() -> Vec<LanguageProfile> {
+    let mut profiles: Vec<LanguageProfile> = language_weights()
+        .into_iter()
+        .map(|(name, weights)| LanguageProfile {
+            name: name.to_string(),
+            description: None,
+            languages: PRESET_LANGUAGES
+                .iter()
+                .zip(weights)
+                .map(|(&language, weight)| ProfileLanguage {
+                    name: language.to_string(),
+                    weight,
+                    transliterator: None,
+                })
+                .collect(),
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// Load profiles from a TOML or JSON file; the format is chosen by extension
+/// and a file may hold a single profile or a list of them
+pub fn load_profiles(path: &Path) -> anyhow::Result<Vec<LanguageProfile>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read profiles from {}: {}", path.display(), e))?;
+
+    let file: ProfileFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+
+    let profiles = match file {
+        ProfileFile::Many { profile } => profile,
+        ProfileFile::List(profiles) => profiles,
+        ProfileFile::Single(profile) => vec![profile],
+    };
+
+    for profile in &profiles {
+        if profile.languages.is_empty() {
+            anyhow::bail!("Profile '{}' has no languages", profile.name);
+        }
+        profile.transliterators()?;
+    }
+    Ok(profiles)
+}
+
+/// Find a profile by name; profiles loaded from files shadow built-in ones
+pub fn find_profile(name: &str, loaded: &[LanguageProfile]) -> Option<LanguageProfile> {
+    loaded
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .or_else(|| builtin_profiles().into_iter().find(|p| p.name == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::write_temp;
+
+    #[test]
+    fn builtin_presets_are_profiles() {
+        let profile = find_profile("1995", &[]).unwrap();
+        assert_eq!(profile.language_names(), PRESET_LANGUAGES);
+        assert_eq!(profile.weights()[0], 0.347);
+        assert!(!profile.has_transliterators());
+        assert!(find_profile("2525", &[]).is_none());
+    }
+
+    #[test]
+    fn loads_profiles_from_toml_and_json() {
+        let toml_path = write_temp(
+            "profiles.toml",
+            "[[profile]]\nname = \"1995\"\n[[profile.language]]\nname = \"English\"\nweight = 1.0\ntransliterator = \"english\"\n",
+        );
+        let loaded = load_profiles(&toml_path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded[0].has_transliterators());
+        // Loaded profiles shadow the built-in preset of the same name
+        assert_eq!(find_profile("1995", &loaded).unwrap().weights(), [1.0]);
+
+        let json_path = write_temp(
+            "profile.json",
+            r#"{"name": "pair", "languages": [{"name": "A", "weight": 0.5}, {"name": "B", "weight": 0.5}]}"#,
+        );
+        let loaded = load_profiles(&json_path).unwrap();
+        assert_eq!(loaded[0].language_names(), ["A", "B"]);
+
+        fs::remove_file(toml_path).unwrap();
+        fs::remove_file(json_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_profiles() {
+        let malformed = write_temp("malformed.toml", "[[profile]]\nname = \"x\"\nlanguage = 3\n");
+        assert!(load_profiles(&malformed).is_err());
+
+        let empty = write_temp("empty.toml", "[[profile]]\nname = \"empty\"\nlanguage = []\n");
+        let error = load_profiles(&empty).unwrap_err();
+        assert_eq!(error.to_string(), "Profile 'empty' has no languages");
+
+        let unknown = write_temp(
+            "unknown.toml",
+            "[[profile]]\nname = \"x\"\n[[profile.language]]\nname = \"X\"\nweight = 1.0\ntransliterator = \"klingon\"\n",
+        );
+        assert!(load_profiles(&unknown).is_err());
+
+        for path in [malformed, empty, unknown] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::config::SIMILARITIES;
+
+/// Names of the built-in tables, default first
+pub const SIMILARITY_PRESETS: [&str; 3] = ["classic", "voicing", "obstruents"];
+
+/// Letters a candidate letter may be confused with when checking clashes
+/// with existing gismu
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimilarityTable {
+    pub name: String,
+    /// For each candidate letter, the gismu letters it clashes with
+    pub letters: BTreeMap<char, BTreeSet<char>>,
+}
+
+/// On-disk form: `letters` maps a letter to a string of similar letters
+#[derive(Debug, Deserialize)]
+struct SimilarityFile {
+    name: Option<String>,
+    #[serde(default)]
+    symmetric: bool,
+    letters: BTreeMap<char, String>,
+}
+
+impl SimilarityTable {
+    pub fn from_entries<'a>(name: &str, entries: impl IntoIterator<Item = (char, &'a str)>) -> Self {
+        let mut letters: BTreeMap<char, BTreeSet<char>> = BTreeMap::new();
+        for (letter, similar) in entries {
+            letters.entry(letter).or_default().extend(similar.chars());
+        }
+        Self {
+            name: name.to_string(),
+            letters,
+        }
+    }
+
+    /// Table of the original gismu-making algorithm
+    pub fn classic() -> Self {
+        Self::from_entries("classic", SIMILARITIES.iter().copied())
+    }
+
+    /// Add the reverse of every pair, so that a~b implies b~a
+    pub fn symmetric(mut self) -> Self {
+        let pairs: Vec<(char, char)> = self
+            .letters
+            .iter()
+            .flat_map(|(&a, similar)| similar.iter().map(move |&b| (b, a)))
+            .collect();
+        for (a, b) in pairs {
+            self.letters.entry(a).or_default().insert(b);
+        }
+        self
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.letters
+            .iter()
+            .all(|(&a, similar)| similar.iter().all(|b| self.is_similar(*b, a)))
+    }
+
+    /// True when `candidate_letter` in a candidate clashes with
+    /// `gismu_letter` at the same position of a gismu
+    pub fn is_similar(&self, candidate_letter: char, gismu_letter: char) -> bool {
+        self.letters
+            .get(&candidate_letter.to_ascii_lowercase())
+            .is_some_and(|similar| similar.contains(&gismu_letter))
+    }
+}
+
+impl Default for SimilarityTable {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+/// `name: b:pv c:js ...`
+impl fmt::Display for SimilarityTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+        for (letter, similar) in &self.letters {
+            write!(f, " {}:{}", letter, similar.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+/// Built-in table by name
+pub fn similarity_preset(name: &str) -> Option<SimilarityTable> {
+    let classic = SimilarityTable::classic();
+    match name {
+        "classic" => Some(classic),
+        // Voiced and unvoiced counterparts only
+        "voicing" => Some(SimilarityTable::from_entries(
+            "voicing",
+            [
+                ('b', "p"),
+                ('p', "b"),
+                ('d', "t"),
+                ('t', "d"),
+                ('g', "k"),
+                ('k', "g"),
+                ('v', "f"),
+                ('f', "v"),
+                ('j', "c"),
+                ('c', "j"),
+                ('z', "s"),
+                ('s', "z"),
+            ],
+        )),
+        // Classic table without the l/r and m/n pairs
+        "obstruents" => Some(SimilarityTable {
+            name: "obstruents".to_string(),
+            letters: classic
+                .letters
+                .into_iter()
+                .filter(|(letter, _)| !"lmnr".contains(*letter))
+                .collect(),
+        }),
+        _ => None,
+    }
+}
+
+/// Load a table from a TOML or JSON file; the format is chosen by extension.
+/// The file has a `letters` map such as `b = "pv"`, an optional `name` and
+/// `symmetric = true` to add the reverse of every pair
+pub fn load_similarity_table(path: &Path) -> anyhow::Result<SimilarityTable> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read similarity table {}: {}", path.display(), e))?;
+
+    let file: SimilarityFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    let name = file
+        .name
+        .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+        .unwrap_or_else(|| "custom".to_string());
+
+    let table = SimilarityTable::from_entries(&name, file.letters.iter().map(|(&c, s)| (c, s.as_str())));
+    Ok(if file.symmetric { table.symmetric() } else { table })
+}
+
+/// Preset name or path to a table file
+pub fn resolve_similarity_table(spec: &str) -> anyhow::Result<SimilarityTable> {
+    match similarity_preset(spec) {
+        Some(table) => Ok(table),
+        None if Path::new(spec).exists() => load_similarity_table(Path::new(spec)),
+        None => anyhow::bail!(
+            "Unknown similarity table '{}'; use a file or one of: {}",
+            spec,
+            SIMILARITY_PRESETS.join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::write_temp;
+
+    #[test]
+    fn presets_and_symmetry() {
+        let classic = SimilarityTable::classic();
+        assert!(classic.is_similar('b', 'p'));
+        assert!(classic.is_similar('B', 'v'));
+        assert!(!classic.is_similar('b', 'k'));
+
+        let voicing = similarity_preset("voicing").unwrap();
+        assert!(voicing.is_symmetric());
+        let obstruents = similarity_preset("obstruents").unwrap();
+        assert!(!obstruents.is_similar('l', 'r'));
+        assert!(similarity_preset("nonexistent").is_none());
+    }
+
+    #[test]
+    fn loads_toml_and_json_tables() {
+        let toml_path = write_temp("table.toml", "name = \"mine\"\nsymmetric = true\n[letters]\nb = \"p\"\n");
+        let table = load_similarity_table(&toml_path).unwrap();
+        assert_eq!(table.name, "mine");
+        assert!(table.is_similar('b', 'p') && table.is_similar('p', 'b'));
+
+        let json_path = write_temp("table.json", r#"{"letters": {"k": "g"}}"#);
+        let table = resolve_similarity_table(json_path.to_str().unwrap()).unwrap();
+        assert_eq!(table.name, format!("vlazba-{}-table", std::process::id()));
+        assert!(table.is_similar('k', 'g') && !table.is_similar('g', 'k'));
+
+        fs::remove_file(toml_path).unwrap();
+        fs::remove_file(json_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_tables() {
+        let path = write_temp("broken.toml", "letters = \"bp\"\n");
+        assert!(load_similarity_table(&path).is_err());
+        fs::remove_file(path).unwrap();
+
+        let error = resolve_similarity_table("no-such-table").unwrap_err();
+        assert!(error.to_string().contains("Unknown similarity table 'no-such-table'"));
+    }
+}
@@ -1,29 +1,77 @@
 use clap::{Arg, Command};
-use jvozba::{jvokaha, tools::search_selrafsi_from_rafsi2, jvozba};
 use rayon::prelude::*;
 use std::{
-    collections::HashSet,
+    collections::{BTreeSet, HashSet},
     fs::File,
     io::{self, BufRead, BufReader},
-    sync::Arc,
+    path::Path,
+    sync::{Arc, OnceLock},
 };
-use smallvec::SmallVec;
 
-mod libs;
-use libs::{cli::{generate_weights, validate_words}, config::{C, DEFAULT_WEIGHTS_STR, V, VERSION}};
+use vlazba::cmevla::{CmevlaCandidate, CmevlaGenerator};
+use vlazba::fuhivla::{FuhivlaCandidate, FuhivlaGenerator, FuhivlaStage};
+use vlazba::gismu_utils::{
+    DyadKind, GismuGenerator, GismuMatcher, GismuScorer, LetterConstraints, ScoreExplanation,
+    ScoredCandidate, Shape, letters_for_words,
+};
+use vlazba::jvozba::{
+    jvokaha, jvozba,
+    rafsi_space::{rafsi_space_report, RafsiSlot, RafsiSpaceReport, RafsiStatus},
+    tools::search_selrafsi_from_rafsi2,
+};
+use vlazba::libs::{
+    backtest::{load_backtest_table, run_backtest, summarize, BacktestConfig, BacktestResult, BacktestSummary},
+    cli::{default_language_names, generate_weights, normalize_weights, validate_words},
+    config::{C, DEFAULT_WEIGHTS_STR, V, VERSION},
+    population::{
+        compare_weights, derive_profile, load_population_table, WeightComparison, WeightFormula,
+    },
+    profile::{builtin_profiles, find_profile, load_profiles, LanguageProfile},
+    similarity_table::resolve_similarity_table,
+};
+use vlazba::morphology::{classify, tokenize, ValsiKind};
+use vlazba::pronunciation::pronounce;
+use vlazba::similarity::metric_from_name;
+use vlazba::transliteration::{
+    ipa::IpaConverter,
+    parse_transliterators,
+    script::{to_script, Script, SCRIPT_NAMES},
+    Transliterator,
+};
 
-mod gismu_utils;
-use gismu_utils::{GismuGenerator, GismuMatcher, GismuScorer};
-mod jvozba;
+static OUTPUT_SCRIPT: OnceLock<Script> = OnceLock::new();
 
 fn log(msg: &str) {
     eprintln!("{}", msg);
 }
 
+/// Lojban text in the script chosen with `--script`
+fn lojban(text: &str) -> String {
+    to_script(text, OUTPUT_SCRIPT.get().copied().unwrap_or_default())
+}
+
+/// Print JSON with the string values under `keys` in the output script
+fn print_json<T: serde::Serialize>(value: &T, keys: &[&str]) -> anyhow::Result<()> {
+    fn convert(value: &mut serde_json::Value, keys: &[&str], in_key: bool) {
+        match value {
+            serde_json::Value::String(text) if in_key => *text = lojban(text),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| convert(v, keys, in_key)),
+            serde_json::Value::Object(map) => map
+                .iter_mut()
+                .for_each(|(key, v)| convert(v, keys, in_key || keys.contains(&key.as_str()))),
+            _ => {}
+        }
+    }
+    let mut json = serde_json::to_value(value)?;
+    convert(&mut json, keys, false);
+    println!("{}", serde_json::to_string_pretty(&json)?);
+    Ok(())
+}
+
 fn main() -> anyhow::Result<()> {
     let matches = Command::new("Optimized Gismu Generator")
         .version(VERSION)
-        .arg(Arg::new("words").help("Input words"))
+        .arg(Arg::new("words").help("Input words").num_args(1..))
         .arg(
             Arg::new("all-letters")
                 .short('a')
@@ -35,7 +83,27 @@ fn main() -> anyhow::Result<()> {
                 .short('s')
                 .long("shapes")
                 .default_value("ccvcv,cvccv")
-                .help("Shapes for gismu candidates"),
+                .help("Shapes for gismu candidates, e.g. ccvcv, cv'v, [bp]vccv, {sibilant}{sonorant}vcv"),
+        )
+        .arg(
+            Arg::new("starts_with")
+                .long("starts-with")
+                .help("Letters gismu candidates must start with"),
+        )
+        .arg(
+            Arg::new("contains")
+                .long("contains")
+                .help("Letters gismu candidates must contain"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .help("Letters gismu candidates must not contain, e.g. \"x'\""),
+        )
+        .arg(
+            Arg::new("pattern")
+                .long("pattern")
+                .help("Letter per position, ? for any, e.g. k?a??"),
         )
         .arg(
             Arg::new("weights")
@@ -50,6 +118,29 @@ fn main() -> anyhow::Result<()> {
                 .long("deduplicate")
                 .help("Path to gismu list for deduplication"),
         )
+        .arg(
+            Arg::new("skip_clashes")
+                .long("skip-clashes")
+                .requires("deduplicate")
+                .help("Skip candidates similar to a gismu of the --deduplicate list while generating")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("similarities")
+                .long("similarities")
+                .requires("deduplicate")
+                .default_value("classic")
+                .help("Letter similarity table for clash checks: classic, voicing, obstruents or a TOML/JSON file"),
+        )
+        .arg(
+            Arg::new("symmetric_similarities")
+                .long("symmetric-similarities")
+                .requires("deduplicate")
+                .help("Make the similarity table symmetric, so that a~b implies b~a")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("jvozba")
                 .long("jvozba")
@@ -71,6 +162,47 @@ fn main() -> anyhow::Result<()> {
                 .num_args(0)
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("pronounce")
+                .long("pronounce")
+                .help("Show syllables, stress and IPA of jvozba and jvokaha results")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("fuhivla")
+                .long("fuhivla")
+                .help("Generate fu'ivla candidates from a source word")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("category")
+                .long("category")
+                .requires("fuhivla")
+                .help("Category gismu whose rafsi prefixes stage-3 fu'ivla, e.g. spati"),
+        )
+        .arg(
+            Arg::new("cmevla")
+                .long("cmevla")
+                .help("Generate cmevla (name) candidates from a foreign name")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("classify")
+                .long("classify")
+                .help("Classify each input word as gismu, cmavo, lujvo, fu'ivla or cmevla")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tokenize")
+                .long("tokenize")
+                .help("Split Lojban text into words with their byte spans and classes")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("jvokaha")
                 .long("jvokaha")
@@ -78,30 +210,297 @@ fn main() -> anyhow::Result<()> {
                 .num_args(0)
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("top")
+                .short('k')
+                .long("top")
+                .help("Number of best gismu candidates to keep while scoring")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("metric")
+                .long("metric")
+                .help("Similarity metric for gismu scoring: lcs, feature, skeleton, position")
+                .default_value("lcs"),
+        )
+        .arg(
+            Arg::new("backtest")
+                .long("backtest")
+                .help("CSV of historical gismu and their source words to rank against generated candidates"),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .help("Explain the score of every reported gismu candidate")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ipa")
+                .long("ipa")
+                .help("Input words are IPA transcriptions to convert to Lojban letters")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ipa_fallback")
+                .long("ipa-fallback")
+                .requires("ipa")
+                .help("Override IPA conversions, e.g. \"θ=f,ð=v\""),
+        )
+        .arg(
+            Arg::new("translit")
+                .long("translit")
+                .conflicts_with("ipa")
+                .help("Transliterators per input word: none, ipa, pinyin, hindi, russian, arabic, spanish, english"),
+        )
+        .arg(
+            Arg::new("profile")
+                .short('p')
+                .long("profile")
+                .conflicts_with("weights")
+                .help("Language profile to use for weights and transliteration"),
+        )
+        .arg(
+            Arg::new("profiles")
+                .long("profiles")
+                .help("TOML or JSON file with additional language profiles"),
+        )
+        .arg(
+            Arg::new("languages")
+                .short('l')
+                .long("languages")
+                .help("Comma-separated names of the source languages, one per weight"),
+        )
+        .arg(
+            Arg::new("population")
+                .long("population")
+                .conflicts_with_all(["profile", "weights"])
+                .help("CSV of language,l1,l2 speaker numbers to derive weights from"),
+        )
+        .arg(
+            Arg::new("formula")
+                .long("formula")
+                .default_value("l1+l2")
+                .help("Weight formula for --population: l1, l2, l1+l2 or l1+K*l2"),
+        )
+        .arg(
+            Arg::new("compare_preset")
+                .long("compare-preset")
+                .requires("population")
+                .help("Compare weights derived from --population with a profile such as 1995"),
+        )
+        .arg(
+            Arg::new("list_profiles")
+                .long("list-profiles")
+                .help("List available language profiles")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("rafsi_report")
+                .long("rafsi-report")
+                .help("Report how the CVC, CCV, CVV and CV'V rafsi space is used")
+                .num_args(0)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .value_parser(SCRIPT_NAMES)
+                .default_value("latin")
+                .help("Script for Lojban words in the output: latin, cyrillic or zbalermorna"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["text", "json", "csv", "tsv"])
+                .default_value("text")
+                .help("Output format for reports and gismu candidates, written to stdout"),
+        )
         .get_matches();
 
+    let script: Script = matches.get_one::<String>("script").unwrap().parse()?;
+    OUTPUT_SCRIPT.set(script).ok();
+
+    if matches.get_flag("rafsi_report") {
+        let report = rafsi_space_report();
+        match matches.get_one::<String>("format").map(String::as_str) {
+            Some("json") => print_json(&report, &["rafsi", "selrafsi"])?,
+            Some("csv") => print_rafsi_slots(&report, ','),
+            Some("tsv") => print_rafsi_slots(&report, '\t'),
+            _ => print_rafsi_report(&report),
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("list_profiles") {
+        let loaded = match matches.get_one::<String>("profiles") {
+            Some(path) => load_profiles(Path::new(path))?,
+            None => Vec::new(),
+        };
+        for profile in loaded.iter().chain(builtin_profiles().iter()) {
+            println!(
+                "{}: {}",
+                profile.name,
+                profile
+                    .languages
+                    .iter()
+                    .map(|l| match &l.transliterator {
+                        Some(t) => format!("{} {} ({})", l.name, l.weight, t),
+                        None => format!("{} {}", l.name, l.weight),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        return Ok(());
+    }
+
     if matches.get_flag("jvozba") {
-        let words: Vec<String> = matches
-            .get_one::<String>("words")
-            .map(|s| s.split_whitespace().map(|word| word.to_string()).collect())
-            .unwrap_or_default();
+        let words = input_words(&matches);
 
         let forbid_la_lai_doi = matches.get_flag("forbid_la_lai_doi");
         let exp_rafsi = matches.get_flag("exp_rafsi");
         let results = jvozba(&words, forbid_la_lai_doi, exp_rafsi);
+        let pronounce_results = matches.get_flag("pronounce");
         for result in results {
-            log(&format!("{}: {}", result.lujvo, result.score));
+            if pronounce_results {
+                log(&format!(
+                    "{}: {} {}",
+                    lojban(&result.lujvo),
+                    result.score,
+                    pronounce_in_script(&result.lujvo)
+                ));
+            } else {
+                log(&format!("{}: {}", lojban(&result.lujvo), result.score));
+            }
         }
         return Ok(());
     }
 
-    if matches.get_flag("jvokaha") {
-        let words: &str = matches
-            .get_one::<String>("words")
-            .map(String::as_str)
-            .unwrap_or("");
+    if matches.get_flag("fuhivla") {
+        let source = transliterate_words(&matches, input_words(&matches), None)?.join("");
+        let mut generator = FuhivlaGenerator::new(&source);
+        if let Some(category) = matches.get_one::<String>("category") {
+            generator = generator.with_category(category)?;
+        }
+        let top = *matches.get_one::<u64>("top").unwrap() as usize;
+        let candidates: Vec<FuhivlaCandidate> = generator.candidates().into_iter().take(top).collect();
+        if candidates.is_empty() {
+            anyhow::bail!(
+                "No valid fu'ivla found for '{}'; a --category gismu allows stage-3 forms",
+                generator.source()
+            );
+        }
 
-        let results = jvokaha::jvokaha(words);
+        let records: Vec<Vec<Cell>> = candidates
+            .iter()
+            .map(|candidate| {
+                vec![
+                    Cell::Lojban(candidate.word.clone()),
+                    Cell::Text(fuhivla_stage_name(candidate.stage).to_string()),
+                    Cell::Number(candidate.score),
+                ]
+            })
+            .collect();
+        if !print_records(&matches, &["word", "stage", "score"], &records)? {
+            log(&format!("fu'ivla candidates for {}:", lojban(generator.source())));
+            for candidate in &candidates {
+                log(&format!(
+                    "{:<16} stage {} {:.3}",
+                    lojban(&candidate.word),
+                    fuhivla_stage_name(candidate.stage),
+                    candidate.score
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("cmevla") {
+        let words = input_words(&matches);
+        // Names are spelled by English rules unless a transliterator is given
+        let generator = if matches.get_flag("ipa") || matches.contains_id("translit") {
+            let source = transliterate_words(&matches, words, None)?.join("");
+            CmevlaGenerator::new(&source).with_transliterator(Transliterator::None)
+        } else {
+            CmevlaGenerator::new(&words.join(""))
+        };
+        let top = *matches.get_one::<u64>("top").unwrap() as usize;
+        let candidates: Vec<CmevlaCandidate> = generator.candidates().into_iter().take(top).collect();
+        if candidates.is_empty() {
+            anyhow::bail!("No valid cmevla found for '{}'", generator.source());
+        }
+
+        let records: Vec<Vec<Cell>> = candidates
+            .iter()
+            .map(|candidate| vec![Cell::Lojban(candidate.word.clone()), Cell::Number(candidate.score)])
+            .collect();
+        if !print_records(&matches, &["word", "score"], &records)? {
+            log(&format!("cmevla candidates for {}:", lojban(generator.source())));
+            for candidate in &candidates {
+                log(&format!("{:<16} {:.3}", lojban(&candidate.word), candidate.score));
+            }
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("classify") {
+        let words = input_words(&matches);
+        let kinds: Vec<ValsiKind> = words.iter().map(|word| classify(word)).collect();
+        let records: Vec<Vec<Cell>> = words
+            .iter()
+            .zip(&kinds)
+            .map(|(word, kind)| {
+                vec![
+                    Cell::Lojban(word.clone()),
+                    Cell::Text(kind.name().to_string()),
+                    Cell::Text(valsi_detail(kind)),
+                ]
+            })
+            .collect();
+        if !print_records(&matches, &["word", "kind", "detail"], &records)? {
+            for (word, kind) in words.iter().zip(&kinds) {
+                log(&format!("{}: {}", lojban(word), valsi_label(kind)));
+            }
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("tokenize") {
+        let text = input_words(&matches).join(" ");
+        let tokens = tokenize(&text);
+        let records: Vec<Vec<Cell>> = tokens
+            .iter()
+            .map(|token| {
+                vec![
+                    Cell::Lojban(token.text.clone()),
+                    Cell::Count(token.span.start),
+                    Cell::Count(token.span.end),
+                    Cell::Text(token.kind.name().to_string()),
+                    Cell::Text(valsi_detail(&token.kind)),
+                ]
+            })
+            .collect();
+        if !print_records(&matches, &["word", "start", "end", "kind", "detail"], &records)? {
+            for token in &tokens {
+                log(&format!(
+                    "{}..{} {}: {}",
+                    token.span.start,
+                    token.span.end,
+                    lojban(&token.text),
+                    valsi_label(&token.kind)
+                ));
+            }
+        }
+        return Ok(());
+    }
+
+    if matches.get_flag("jvokaha") {
+        let words = input_words(&matches).join("");
+        let results = jvokaha::jvokaha(&words);
 
         match results {
             Ok(result) => {
@@ -111,8 +510,8 @@ fn main() -> anyhow::Result<()> {
                     .filter(|a| a.len() > 1)
                     .map(|rafsi| {
                         match search_selrafsi_from_rafsi2(&rafsi, exp_rafsi) {
-                            Some(selrafsi) => selrafsi,
-                            None => format!("-{}-", rafsi), // output as rafsi form; signify as unknown
+                            Some(selrafsi) => lojban(&selrafsi),
+                            None => format!("-{}-", lojban(&rafsi)), // output as rafsi form; signify as unknown
                         }
                     })
                     .collect();
@@ -120,6 +519,9 @@ fn main() -> anyhow::Result<()> {
                 for (index, rafsi) in arr.iter().enumerate() {
                     log(&format!("  {}: {}", index + 1, rafsi));
                 }
+                if matches.get_flag("pronounce") {
+                    log(&format!("Pronunciation: {}", pronounce_in_script(&words)));
+                }
             }
             Err(e) => {
                 log(&format!("Error: {}", e));
@@ -128,10 +530,44 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    let words: Vec<String> = matches
-        .get_one::<String>("words")
-        .map(|s| s.split_whitespace().map(|word| word.to_string()).collect())
-        .unwrap_or_default();
+    let words = input_words(&matches);
+    let loaded_profiles = match matches.get_one::<String>("profiles") {
+        Some(path) => load_profiles(Path::new(path))?,
+        None => Vec::new(),
+    };
+    let weights_str = matches.get_one::<String>("weights").unwrap();
+    let profile = if let Some(path) = matches.get_one::<String>("population") {
+        let formula: WeightFormula = matches.get_one::<String>("formula").unwrap().parse()?;
+        let path = Path::new(path);
+        let table = load_population_table(path)?;
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("population");
+        let derived = derive_profile(name, &table, formula)?;
+        if let Some(name) = matches.get_one::<String>("compare_preset") {
+            let reference = find_profile(name, &loaded_profiles)
+                .ok_or_else(|| anyhow::anyhow!("No profile named {}", name))?;
+            print_weight_comparison(&compare_weights(&derived, &reference), &reference.name);
+        }
+        if words.is_empty() {
+            for language in &derived.languages {
+                println!("{},{}", language.name, language.weight);
+            }
+            return Ok(());
+        }
+        Some(derived)
+    } else {
+        match matches.get_one::<String>("profile") {
+            Some(name) => Some(
+                find_profile(name, &loaded_profiles)
+                    .ok_or_else(|| anyhow::anyhow!("No profile named {}", name))?,
+            ),
+            None => find_profile(weights_str.trim(), &loaded_profiles),
+        }
+    };
+    let backtest_path = matches.get_one::<String>("backtest");
+    let words = match backtest_path {
+        Some(_) => words,
+        None => transliterate_words(&matches, words, profile.as_ref())?,
+    };
     let all_letters = matches.contains_id("all-letters");
     let shapes: Vec<String> = matches
         .get_one::<String>("shapes")
@@ -140,11 +576,84 @@ fn main() -> anyhow::Result<()> {
         .map(str::trim)
         .map(|s| s.to_string())
         .collect();
-    let weights = generate_weights(matches.get_one::<String>("weights").unwrap())?;
+    let parsed_shapes = shapes
+        .iter()
+        .map(|shape| {
+            shape
+                .parse::<Shape>()
+                .map(|parsed| (shape.clone(), parsed))
+                .map_err(|e| anyhow::anyhow!("Invalid shape '{}': {}", shape, e))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let (languages, weights) = match &profile {
+        Some(profile) => {
+            log(&format!(
+                "Using profile {}: {}.",
+                profile.name,
+                profile
+                    .languages
+                    .iter()
+                    .map(|l| format!("{} {}", l.name, l.weight))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            (profile.language_names(), profile.weights())
+        }
+        None => {
+            let weights = generate_weights(weights_str)?;
+            (default_language_names(weights.len()), weights)
+        }
+    };
+    let languages: Vec<String> = match matches.get_one::<String>("languages") {
+        Some(names) => names.split(',').map(|name| name.trim().to_string()).collect(),
+        None => languages,
+    };
+    let (weights, warnings) = normalize_weights(&weights, &languages)?;
+    for warning in warnings {
+        log(&format!("Warning: {}", warning));
+    }
+
+    if let Some(path) = backtest_path {
+        let entries = load_backtest_table(Path::new(path), languages.len())?;
+        let config = BacktestConfig {
+            weights,
+            shapes,
+            metric: matches.get_one::<String>("metric").unwrap().clone(),
+            all_letters,
+        };
+        log(&format!("Backtesting {} gismu...", entries.len()));
+        let results = run_backtest(&entries, &config)?;
+        let summary = summarize(&results);
+        match matches.get_one::<String>("format").map(String::as_str) {
+            Some("json") => print_json(
+                &serde_json::json!({
+                    "summary": summary,
+                    "results": results,
+                }),
+                &["gismu", "winner"],
+            )?,
+            Some("csv") => print_backtest_rows(&results, ','),
+            Some("tsv") => print_backtest_rows(&results, '\t'),
+            _ => print_backtest_report(&results, &summary),
+        }
+        return Ok(());
+    }
 
     let gismu_list_path = matches.get_one::<String>("deduplicate");
 
-    validate_words(&words, &weights)?;
+    validate_words(&words, &languages)?;
+
+    let unusable: BTreeSet<char> = words
+        .iter()
+        .flat_map(|word| word.chars())
+        .filter(|&c| !C.contains(c) && !V.contains(c))
+        .collect();
+    if !unusable.is_empty() {
+        log(&format!(
+            "Letters {} never occur in gismu candidates and are ignored when choosing letters.",
+            unusable.iter().map(char::to_string).collect::<Vec<_>>().join(",")
+        ));
+    }
 
     let (c, v) = if all_letters {
         (
@@ -160,69 +669,634 @@ fn main() -> anyhow::Result<()> {
         v.join(",")
     ));
 
-    let candidate_iterator = GismuGenerator::new(c, v, shapes);
-    let candidates: Vec<String> = candidate_iterator.iterator();
-    log(&format!("{} candidates generated.", candidates.len()));
-
-    let scorer = GismuScorer::new(&words, &weights);
+    let constraints = LetterConstraints {
+        starts_with: matches.get_one::<String>("starts_with").cloned(),
+        contains: matches
+            .get_one::<String>("contains")
+            .map(|s| {
+                s.chars()
+                    .filter(|c| !c.is_whitespace() && *c != ',')
+                    .map(|c| c.to_ascii_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        exclude: matches
+            .get_one::<String>("exclude")
+            .map(|s| {
+                s.chars()
+                    .filter(|c| !c.is_whitespace() && *c != ',')
+                    .map(|c| c.to_ascii_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        pattern: matches.get_one::<String>("pattern").cloned(),
+    };
+    let matcher = match gismu_list_path {
+        Some(gismu_list_path) => {
+            log("Reading list of gismu... ");
+            let gismus = read_gismu_list(gismu_list_path)?;
+            let mut table = resolve_similarity_table(matches.get_one::<String>("similarities").unwrap())?;
+            if matches.get_flag("symmetric_similarities") {
+                table = table.symmetric();
+            }
+            log(&format!(
+                "Similarity table {}{}",
+                table,
+                if table.is_symmetric() { " (symmetric)" } else { "" }
+            ));
+            Some(Arc::new(GismuMatcher::new(&gismus, None).with_similarity_table(table)))
+        }
+        None => None,
+    };
+    let mut candidate_iterator = GismuGenerator::new(c, v, shapes).with_constraints(constraints);
+    if let Some(matcher) = matcher.as_ref().filter(|_| matches.get_flag("skip_clashes")) {
+        log("Skipping candidates similar to existing gismu...");
+        candidate_iterator = candidate_iterator.with_clash_filter(Arc::clone(matcher));
+    }
+    let top = *matches.get_one::<u64>("top").unwrap() as usize;
 
-    let mut scores: Vec<(f32, &String, SmallVec<[f32; 6]>)> = candidates
-    .par_iter()
-    .map(|candidate| scorer.compute_score_with_name(candidate))
-    .collect();
-    
+    let metric = metric_from_name(matches.get_one::<String>("metric").unwrap())?;
+    let scorer = GismuScorer::new(&words, &weights).with_metric(metric);
+    let (generated, scores) = scorer.top_k(candidate_iterator.par_candidates()?, top);
+    log(&format!("{} candidates generated.", generated));
 
-    scores.sort_unstable_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    log(&format!("\n{} first gismu candidates are:\n", scores.len()));
+    print_scores(&scores, &languages);
 
-    log("\n10 first gismu candidates are:\n");
-    for record in scores.iter().take(10) {
-        log(&format!("{:?}", record));
+    if matches.get_flag("explain") {
+        for scored in &scores {
+            print_explanation(&scorer.explain(&scored.candidate), &languages);
+        }
     }
 
-    if let Some(gismu_list_path) = gismu_list_path {
-        log("Reading list of gismu... ");
-        let gismus = read_gismu_list(gismu_list_path)?;
-        let matcher = Arc::new(GismuMatcher::new(&gismus, None));
-        log("Excluding candidates similar to existing gismu...");
-        if let Some(candidate) = deduplicate_candidates(&matcher, &scores) {
-            log("The winner is....");
-            log(&candidate.to_uppercase().to_string());
-        } else {
-            log("No suitable candidates found.");
+    let clashes = match &matcher {
+        Some(matcher) => {
+            log(&format!(
+                "Excluding candidates similar to existing gismu (similarity table {})...",
+                matcher.similarity_table().name
+            ));
+            let clashes: Vec<Option<String>> = scores
+                .par_iter()
+                .map(|scored| matcher.find_similar_gismu(&scored.candidate))
+                .collect();
+            let winner = match deduplicate_candidates(&scores, &clashes) {
+                Some(candidate) => Some(candidate),
+                // Every kept candidate clashes; look further down the ranking
+                None if !matches.get_flag("skip_clashes") => {
+                    log(&format!(
+                        "No suitable candidates among the {} best, scoring the others...",
+                        scores.len()
+                    ));
+                    let remaining = candidate_iterator.with_clash_filter(Arc::clone(matcher));
+                    let (_, best) = scorer.top_k(remaining.par_candidates()?, 1);
+                    best.into_iter().next().map(|scored| scored.candidate)
+                }
+                None => None,
+            };
+            match winner {
+                Some(candidate) => {
+                    log("The winner is....");
+                    log(&lojban(&candidate.to_uppercase()));
+                }
+                None => log("No suitable candidates."),
+            }
+            Some(clashes)
+        }
+        None => None,
+    };
+
+    match matches.get_one::<String>("format").map(String::as_str) {
+        Some("json") => {
+            let records: Vec<serde_json::Value> = scores
+                .iter()
+                .enumerate()
+                .map(|(i, scored)| {
+                    let language_scores: serde_json::Map<String, serde_json::Value> = languages
+                        .iter()
+                        .zip(&scored.similarities)
+                        .map(|(language, &similarity)| (language.clone(), similarity.into()))
+                        .collect();
+                    serde_json::json!({
+                        "candidate": lojban(&scored.candidate),
+                        "score": scored.score,
+                        "shape": shape_of(&scored.candidate, &parsed_shapes),
+                        "scores": language_scores,
+                        "clash": clashes.as_ref().map(|c| c[i].as_deref().map(lojban)),
+                        "similarity_table": matcher.as_ref().map(|m| m.similarity_table().name.clone()),
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&records)?);
         }
+        Some("csv") => print_candidates(&scores, &languages, &parsed_shapes, clashes.as_deref(), ','),
+        Some("tsv") => print_candidates(&scores, &languages, &parsed_shapes, clashes.as_deref(), '\t'),
+        _ => {}
     }
 
     Ok(())
 }
 
-fn letters_for_words(words: &[String]) -> (Vec<String>, Vec<String>) {
-    let word_set: HashSet<char> = words.iter().flat_map(|word| word.chars()).collect();
+/// Words may be given as one quoted argument or as separate arguments
+fn input_words(matches: &clap::ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("words")
+        .map(|values| {
+            values
+                .flat_map(|s| s.split_whitespace())
+                .map(|word| word.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-    (
-        C.chars()
-            .filter(|&c| word_set.contains(&c))
-            .map(|s| s.to_string())
-            .collect(),
-        V.chars()
-            .filter(|&c| word_set.contains(&c))
-            .map(|s| s.to_string())
-            .collect(),
-    )
+fn transliterate_words(
+    matches: &clap::ArgMatches,
+    words: Vec<String>,
+    profile: Option<&LanguageProfile>,
+) -> anyhow::Result<Vec<String>> {
+    let transliterators = if matches.get_flag("ipa") {
+        vec![Transliterator::Ipa; words.len()]
+    } else if let Some(spec) = matches.get_one::<String>("translit") {
+        match parse_transliterators(spec)?.as_slice() {
+            [single] => vec![*single; words.len()],
+            list if list.len() == words.len() => list.to_vec(),
+            list => anyhow::bail!(
+                "Expected 1 or {} transliterators, got {}",
+                words.len(),
+                list.len()
+            ),
+        }
+    } else if let Some(profile) = profile.filter(|p| p.has_transliterators()) {
+        let transliterators = profile.transliterators()?;
+        if transliterators.len() != words.len() {
+            anyhow::bail!("Expected {} words as input", transliterators.len());
+        }
+        transliterators
+    } else {
+        return Ok(words);
+    };
+
+    let converter = IpaConverter::new().with_fallbacks_str(
+        matches
+            .get_one::<String>("ipa_fallback")
+            .map(String::as_str)
+            .unwrap_or(""),
+    )?;
+
+    log("Transliterated input:");
+    Ok(words
+        .iter()
+        .zip(transliterators)
+        .map(|(word, transliterator)| match transliterator {
+            Transliterator::Ipa => {
+                let conversion = converter.convert(word);
+                log(&format!("  {}", conversion));
+                conversion.output
+            }
+            other => {
+                let output = other.transliterate(word);
+                log(&format!("  {} → {} ({})", word, lojban(&output), other));
+                output
+            }
+        })
+        .collect())
 }
 
-fn deduplicate_candidates(
-    matcher: &Arc<GismuMatcher>,
-    scores: &[(f32, &String, SmallVec<[f32; 6]>)],
-) -> Option<String> {
-    scores.par_iter().find_map_any(|(_, candidate, _)| {
-        matcher.find_similar_gismu(candidate).map(|gismu| {
-            log(&format!(
-                "Candidate '{}' too much like gismu '{}'.",
-                candidate, gismu
+fn print_rafsi_report(report: &RafsiSpaceReport) {
+    println!(
+        "{:<6} {:>6} {:>9} {:>13} {:>6} {:>11}",
+        "shape", "total", "official", "experimental", "free", "duplicates"
+    );
+    for s in &report.summary {
+        println!(
+            "{:<6} {:>6} {:>9} {:>13} {:>6} {:>11}",
+            s.shape, s.total, s.official, s.experimental, s.free, s.duplicates
+        );
+    }
+
+    let describe = |slot: &RafsiSlot| {
+        slot.owners
+            .iter()
+            .map(|o| {
+                if o.source.is_official() {
+                    lojban(&o.selrafsi)
+                } else {
+                    format!("{} (exp)", lojban(&o.selrafsi))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if !report.duplicates.is_empty() {
+        println!("\nDuplicate assignments:");
+        for slot in &report.duplicates {
+            println!("  {}: {}", lojban(&slot.rafsi), describe(slot));
+        }
+    }
+
+    if !report.unlisted.is_empty() {
+        println!("\nRafsi outside the enumerated forms:");
+        for slot in &report.unlisted {
+            println!("  {}: {}", lojban(&slot.rafsi), describe(slot));
+        }
+    }
+}
+
+fn print_weight_comparison(comparison: &[WeightComparison], reference: &str) {
+    log(&format!("{:<12} {:>8} {:>8} {:>8}", "language", "derived", reference, "diff"));
+    for row in comparison {
+        match row.reference {
+            Some(weight) => log(&format!(
+                "{:<12} {:>8.3} {:>8.3} {:>+8.3}",
+                row.language,
+                row.derived,
+                weight,
+                row.derived - weight
+            )),
+            None => log(&format!("{:<12} {:>8.3} {:>8}", row.language, row.derived, "-")),
+        }
+    }
+}
+
+/// Show matched letters in upper case
+fn mark_alignment(word: &str, positions: impl Iterator<Item = usize>) -> String {
+    let positions: HashSet<usize> = positions.collect();
+    word.char_indices()
+        .map(|(i, c)| if positions.contains(&i) { c.to_ascii_uppercase() } else { c })
+        .collect()
+}
+
+fn print_explanation(explanation: &ScoreExplanation, languages: &[String]) {
+    for line in explanation_lines(explanation, languages) {
+        log(&line);
+    }
+}
+
+/// Lines of `--explain` output, with the candidate in the output script
+fn explanation_lines(explanation: &ScoreExplanation, languages: &[String]) -> Vec<String> {
+    let mut lines = vec![format!("\n{}: {:.4}", lojban(&explanation.candidate), explanation.score)];
+    let language_width = languages.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let word_width = explanation.words.iter().map(|w| w.word.chars().count()).max().unwrap_or(0);
+
+    if explanation.metric != "lcs" {
+        for (language, word) in languages.iter().zip(&explanation.words) {
+            lines.push(format!(
+                "  {:<lw$} {} {:<ww$} {} {:.3} × {:.3} = {:.4}",
+                language,
+                lojban(&explanation.candidate),
+                word.word,
+                explanation.metric,
+                word.normalized_score,
+                word.weight,
+                word.contribution,
+                lw = language_width,
+                ww = word_width
             ));
-            (*candidate).to_string()
+        }
+        return lines;
+    }
+
+    for (language, word) in languages.iter().zip(&explanation.words) {
+        let rule = match (&word.dyad, word.alignment.len()) {
+            (Some(dyad), _) => format!(
+                "dyad '{}' {} in source",
+                lojban(&dyad.dyad),
+                match dyad.kind {
+                    DyadKind::Gapped => "gapped",
+                    DyadKind::Adjacent => "adjacent",
+                }
+            ),
+            (None, 2) => "dyad rule failed".to_string(),
+            (None, 0 | 1) => "too few letters".to_string(),
+            (None, _) => "counted".to_string(),
+        };
+        lines.push(format!(
+            "  {:<lw$} {} {:<ww$} lcs {} ({}); {}/{} = {:.3} × {:.3} = {:.4}",
+            language,
+            lojban(&mark_alignment(&explanation.candidate, word.alignment.iter().map(|&(i, _)| i))),
+            mark_alignment(&word.word, word.alignment.iter().map(|&(_, j)| j)),
+            word.alignment.len(),
+            rule,
+            word.raw_score,
+            word.word.len(),
+            word.normalized_score,
+            word.weight,
+            word.contribution,
+            lw = language_width,
+            ww = word_width
+        ));
+    }
+    lines
+}
+
+fn print_scores(scores: &[ScoredCandidate], languages: &[String]) {
+    let candidates: Vec<String> = scores.iter().map(|scored| lojban(&scored.candidate)).collect();
+    let candidate_width = candidates
+        .iter()
+        .map(|candidate| candidate.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("candidate".len());
+    let widths: Vec<usize> = languages.iter().map(|l| l.chars().count().max(6)).collect();
+
+    log(&format!(
+        "{:<cw$} {:>7} {}",
+        "candidate",
+        "score",
+        languages
+            .iter()
+            .zip(&widths)
+            .map(|(language, &w)| format!("{:>w$}", language))
+            .collect::<Vec<_>>()
+            .join(" "),
+        cw = candidate_width
+    ));
+    for (scored, candidate) in scores.iter().zip(&candidates) {
+        log(&format!(
+            "{:<cw$} {:>7.4} {}",
+            candidate,
+            scored.score,
+            scored
+                .similarities
+                .iter()
+                .zip(&widths)
+                .map(|(similarity, &w)| format!("{:>w$.3}", similarity))
+                .collect::<Vec<_>>()
+                .join(" "),
+            cw = candidate_width
+        ));
+    }
+}
+
+/// First candidate, in ranked order, that does not clash with an existing
+/// gismu. Earlier versions announced a clashing candidate as the winner instead
+fn deduplicate_candidates(scores: &[ScoredCandidate], clashes: &[Option<String>]) -> Option<String> {
+    scores
+        .iter()
+        .zip(clashes)
+        .find_map(|(scored, clash)| match clash {
+            Some(gismu) => {
+                log(&format!(
+                    "Candidate '{}' too much like gismu '{}'.",
+                    lojban(&scored.candidate),
+                    lojban(gismu)
+                ));
+                None
+            }
+            None => Some(scored.candidate.clone()),
         })
-    })
+}
+
+/// First shape, as given on the command line, that a candidate fits
+fn shape_of<'a>(candidate: &str, shapes: &'a [(String, Shape)]) -> &'a str {
+    shapes
+        .iter()
+        .find(|(_, shape)| shape.matches(candidate))
+        .map_or("", |(name, _)| name.as_str())
+}
+
+/// Quote a field for CSV when needed; TSV fields cannot hold tabs or newlines
+fn delimited_field(field: &str, separator: char) -> String {
+    if separator == '\t' {
+        field.replace(['\t', '\n', '\r'], " ")
+    } else if field.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn print_delimited<I, S>(fields: I, separator: char)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let line: Vec<String> = fields
+        .into_iter()
+        .map(|field| delimited_field(field.as_ref(), separator))
+        .collect();
+    println!("{}", line.join(&separator.to_string()));
+}
+
+/// Value of one column of a `--format` record
+enum Cell {
+    /// Lojban text, written in the output script
+    Lojban(String),
+    Text(String),
+    Number(f32),
+    Count(usize),
+}
+
+impl Cell {
+    fn text(&self) -> String {
+        match self {
+            Cell::Lojban(text) => lojban(text),
+            Cell::Text(text) => text.clone(),
+            Cell::Number(number) => number.to_string(),
+            Cell::Count(count) => count.to_string(),
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        match self {
+            // Through the shortest decimal form, so 0.8 is not written as 0.800000011920929
+            Cell::Number(number) => number
+                .to_string()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Cell::Count(count) => (*count).into(),
+            _ => self.text().into(),
+        }
+    }
+}
+
+/// Print records for `--format json`, `csv` or `tsv`, one per row or object
+/// with the given column names; false for text output, which every mode
+/// writes in its own layout
+fn print_records(matches: &clap::ArgMatches, columns: &[&str], records: &[Vec<Cell>]) -> anyhow::Result<bool> {
+    match matches.get_one::<String>("format").map(String::as_str) {
+        Some("json") => {
+            let objects: Vec<serde_json::Map<String, serde_json::Value>> = records
+                .iter()
+                .map(|record| {
+                    columns
+                        .iter()
+                        .zip(record)
+                        .map(|(column, cell)| (column.to_string(), cell.json()))
+                        .collect()
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&objects)?);
+        }
+        Some(format @ ("csv" | "tsv")) => {
+            let separator = if format == "csv" { ',' } else { '\t' };
+            print_delimited(columns, separator);
+            for record in records {
+                print_delimited(record.iter().map(Cell::text), separator);
+            }
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// One row per candidate; the clash column holds the similar gismu, if any,
+/// and is empty when no gismu list was given
+fn print_candidates(
+    scores: &[ScoredCandidate],
+    languages: &[String],
+    shapes: &[(String, Shape)],
+    clashes: Option<&[Option<String>]>,
+    separator: char,
+) {
+    let header = ["candidate", "score", "shape"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(languages.iter().cloned())
+        .chain(std::iter::once("clash".to_string()));
+    print_delimited(header, separator);
+
+    for (i, scored) in scores.iter().enumerate() {
+        let clash = clashes.and_then(|c| c[i].as_deref()).map(lojban).unwrap_or_default();
+        let row = [
+            lojban(&scored.candidate),
+            scored.score.to_string(),
+            shape_of(&scored.candidate, shapes).to_string(),
+        ]
+        .into_iter()
+        .chain(scored.similarities.iter().map(f32::to_string))
+        .chain(std::iter::once(clash));
+        print_delimited(row, separator);
+    }
+}
+
+fn print_backtest_rows(results: &[BacktestResult], separator: char) {
+    print_delimited(
+        ["gismu", "rank", "generated", "candidates", "gismu_score", "winner", "winner_score"],
+        separator,
+    );
+    for result in results {
+        print_delimited(
+            [
+                lojban(&result.gismu),
+                result.rank.to_string(),
+                result.generated.to_string(),
+                result.candidates.to_string(),
+                result.gismu_score.to_string(),
+                result.winner.as_deref().map(lojban).unwrap_or_default(),
+                result.winner_score.map(|s| s.to_string()).unwrap_or_default(),
+            ],
+            separator,
+        );
+    }
+}
+
+fn print_backtest_report(results: &[BacktestResult], summary: &BacktestSummary) {
+    println!(
+        "{:<6} {:>7} {:>10} {:>7}  {:<6} {:>7}",
+        "gismu", "rank", "candidates", "score", "winner", "score"
+    );
+    for result in results {
+        println!(
+            "{:<6} {:>7} {:>10} {:>7.4}  {:<6} {:>7}",
+            lojban(&result.gismu),
+            if result.generated {
+                result.rank.to_string()
+            } else {
+                format!("({})", result.rank)
+            },
+            result.candidates,
+            result.gismu_score,
+            result.winner.as_deref().map_or("-".to_string(), lojban),
+            result
+                .winner_score
+                .map_or("-".to_string(), |s| format!("{:.4}", s)),
+        );
+    }
+
+    let percent = |n: usize| 100.0 * n as f64 / summary.entries.max(1) as f64;
+    println!("\nEntries:            {}", summary.entries);
+    println!(
+        "Generated:          {} ({:.1}%); ranks in parentheses are for gismu the generator cannot produce",
+        summary.generated,
+        percent(summary.generated)
+    );
+    println!("Winner (rank 1):    {} ({:.1}%)", summary.top1, percent(summary.top1));
+    println!("In top 10:          {} ({:.1}%)", summary.top10, percent(summary.top10));
+    println!("Mean rank:          {:.1}", summary.mean_rank);
+    println!("Median rank:        {:.1}", summary.median_rank);
+
+    let differing: Vec<&BacktestResult> = results.iter().filter(|r| r.differs()).collect();
+    if !differing.is_empty() {
+        println!("\nHistorical choice differs from the winner:");
+        for result in differing {
+            println!(
+                "  {} ranked {}, winner {} ({})",
+                lojban(&result.gismu),
+                result.rank,
+                result.winner.as_deref().map_or("-".to_string(), lojban),
+                lojban(&result.words.join(" "))
+            );
+        }
+    }
+}
+
+/// Rafsi, cmavo, fu'ivla stage or the reason a word is invalid
+fn valsi_detail(kind: &ValsiKind) -> String {
+    match kind {
+        ValsiKind::CmavoCluster(cmavo) => lojban(&cmavo.join(" ")),
+        ValsiKind::Lujvo(parts) => lojban(&parts.join("-")),
+        ValsiKind::Fuhivla(stage) => fuhivla_stage_name(*stage).to_string(),
+        ValsiKind::Invalid(reason) => reason.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Word class with its detail, as `ValsiKind` displays it
+fn valsi_label(kind: &ValsiKind) -> String {
+    match kind {
+        ValsiKind::CmavoCluster(_) | ValsiKind::Lujvo(_) => format!("{} ({})", kind.name(), valsi_detail(kind)),
+        _ => kind.to_string(),
+    }
+}
+
+/// Syllables and stressed form in the output script, IPA as is
+fn pronounce_in_script(word: &str) -> String {
+    let pronunciation = pronounce(word);
+    let syllables: Vec<String> = pronunciation.syllables.iter().map(|s| lojban(&s.text)).collect();
+    format!(
+        "{} {} /{}/",
+        syllables.join("."),
+        lojban(&pronunciation.stressed),
+        pronunciation.ipa
+    )
+}
+
+fn fuhivla_stage_name(stage: FuhivlaStage) -> &'static str {
+    match stage {
+        FuhivlaStage::Three => "3",
+        FuhivlaStage::Four => "4",
+    }
+}
+
+/// One row per rafsi form, including those outside the enumerated shapes
+fn print_rafsi_slots(report: &RafsiSpaceReport, separator: char) {
+    print_delimited(["rafsi", "shape", "status", "owners"], separator);
+    for slot in report.slots.iter().chain(&report.unlisted) {
+        let status = match slot.status {
+            RafsiStatus::Official => "official",
+            RafsiStatus::Experimental => "experimental",
+            RafsiStatus::Free => "free",
+        };
+        let owners: Vec<String> = slot.owners.iter().map(|o| lojban(&o.selrafsi)).collect();
+        print_delimited(
+            [lojban(&slot.rafsi).as_str(), slot.shape, status, &owners.join(" ")],
+            separator,
+        );
+    }
 }
 
 fn read_gismu_list(path: &str) -> io::Result<Vec<String>> {
@@ -230,3 +1304,44 @@ fn read_gismu_list(path: &str) -> io::Result<Vec<String>> {
     let reader = BufReader::new(file);
     reader.lines().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shape_of_matches_letter_class() {
+        let shapes: Vec<(String, Shape)> = ["{sibilant}vccv", "ccvcv"]
+            .iter()
+            .map(|s| (s.to_string(), s.parse().unwrap()))
+            .collect();
+        assert_eq!(shape_of("sorta", &shapes), "{sibilant}vccv");
+        assert_eq!(shape_of("zarbu", &shapes), "{sibilant}vccv");
+        assert_eq!(shape_of("blaci", &shapes), "ccvcv");
+        assert_eq!(shape_of("kalbu", &shapes), "");
+    }
+
+    #[test]
+    fn explanation_uses_output_script() {
+        OUTPUT_SCRIPT.set(Script::Cyrillic).ok();
+        assert_eq!(OUTPUT_SCRIPT.get(), Some(&Script::Cyrillic));
+        let words = ["klama".to_string(), "bloti".to_string()];
+        let languages = ["A".to_string(), "B".to_string()];
+        let scorer = GismuScorer::new(&words, &[0.5, 0.5]);
+        let lines = explanation_lines(&scorer.explain("klama"), &languages);
+        assert!(lines[0].starts_with(&format!("\n{}:", to_script("klama", Script::Cyrillic))));
+        let columns: Vec<&str> = lines[1].split_whitespace().collect();
+        assert_eq!(columns[..3], ["A", to_script("KLAMA", Script::Cyrillic).as_str(), "KLAMA"]);
+    }
+
+    #[test]
+    fn explanation_leaves_out_alignment_for_other_metrics() {
+        let words = ["klama".to_string()];
+        let scorer = GismuScorer::new(&words, &[1.0]).with_metric(metric_from_name("feature").unwrap());
+        let explanation = scorer.explain("klama");
+        assert!(explanation.words[0].alignment.is_empty());
+        assert_eq!(explanation.words[0].raw_score, explanation.words[0].normalized_score);
+        let lines = explanation_lines(&explanation, &["A".to_string()]);
+        assert!(lines[1].contains(" klama feature "), "{}", lines[1]);
+    }
+}
@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use crate::cmevla::{check_cmevla, CmevlaError};
+use crate::fuhivla::{fuhivla_stage, is_lujvo_shape, FuhivlaError, FuhivlaStage};
+use crate::jvozba::jvokaha::jvokaha;
+use crate::jvozba::jvozbanarge::{la_lai_doi_positions, LaLaiDoiRule};
+use crate::jvozba::rafsi_shape::rafsi_shape;
+use crate::phonotactics::{check_word, check_word_class, is_consonant, is_vowel, PhonotacticError, WordClass};
+
+/// Word class of a valsi following the morphology of CLL chapter 4
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValsiKind {
+    Gismu,
+    Cmavo,
+    /// Several cmavo written together, e.g. `loinu`
+    CmavoCluster(Vec<String>),
+    /// Rafsi and hyphens found by jvokaha
+    Lujvo(Vec<String>),
+    Fuhivla(FuhivlaStage),
+    Cmevla,
+    Invalid(ValsiError),
+}
+
+impl ValsiKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValsiKind::Gismu => "gismu",
+            ValsiKind::Cmavo => "cmavo",
+            ValsiKind::CmavoCluster(_) => "cmavo cluster",
+            ValsiKind::Lujvo(_) => "lujvo",
+            ValsiKind::Fuhivla(_) => "fu'ivla",
+            ValsiKind::Cmevla => "cmevla",
+            ValsiKind::Invalid(_) => "invalid",
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, ValsiKind::Invalid(_))
+    }
+}
+
+/// `lujvo (kal-ga'u)`, `fu'ivla (stage 4)`, `invalid (reason)`
+impl fmt::Display for ValsiKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValsiKind::CmavoCluster(cmavo) => write!(f, "{} ({})", self.name(), cmavo.join(" ")),
+            ValsiKind::Lujvo(parts) => write!(f, "{} ({})", self.name(), parts.join("-")),
+            ValsiKind::Fuhivla(FuhivlaStage::Three) => write!(f, "{} (stage 3)", self.name()),
+            ValsiKind::Fuhivla(FuhivlaStage::Four) => write!(f, "{} (stage 4)", self.name()),
+            ValsiKind::Invalid(reason) => write!(f, "{} ({})", self.name(), reason),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+/// Why a word is not a valid valsi
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValsiError {
+    Empty,
+    Phonotactics(PhonotacticError),
+    Cmevla(CmevlaError),
+    /// Word splits into rafsi but jvokaha rejects it
+    Lujvo(String),
+    Fuhivla(FuhivlaError),
+}
+
+impl fmt::Display for ValsiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValsiError::Empty => write!(f, "word has no letters"),
+            ValsiError::Phonotactics(e) => write!(f, "{}", e),
+            ValsiError::Cmevla(e) => write!(f, "{}", e),
+            ValsiError::Lujvo(message) => write!(f, "{}", message),
+            ValsiError::Fuhivla(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ValsiError {}
+
+/// Classify a single word. Words ending in a consonant are cmevla, words
+/// without a consonant cluster are cmavo, and the rest are brivla: gismu by
+/// shape, lujvo when they split into rafsi, fu'ivla otherwise
+pub fn classify(word: &str) -> ValsiKind {
+    let word = word.trim().trim_matches('.').to_lowercase();
+    match classify_normalized(&word) {
+        Ok(kind) => kind,
+        Err(e) => ValsiKind::Invalid(e),
+    }
+}
+
+fn classify_normalized(word: &str) -> Result<ValsiKind, ValsiError> {
+    if word.is_empty() {
+        return Err(ValsiError::Empty);
+    }
+    if word.ends_with(is_consonant) || word.contains(',') {
+        return check_cmevla(word).map(|_| ValsiKind::Cmevla).map_err(ValsiError::Cmevla);
+    }
+    // Stage-3 fu'ivla may hold clusters that are only valid at the rafsi junction
+    if let Err(e) = check_word(word) {
+        return match fuhivla_stage(word) {
+            Ok(stage) => Ok(ValsiKind::Fuhivla(stage)),
+            Err(_) => Err(ValsiError::Phonotactics(e)),
+        };
+    }
+
+    let chars: Vec<char> = word.chars().collect();
+    let has_cluster = chars
+        .windows(2)
+        .any(|pair| is_consonant(pair[0]) && is_consonant(pair[1]));
+    if !has_cluster {
+        let cmavo = split_cmavo(word);
+        for piece in &cmavo {
+            check_word_class(piece, WordClass::Cmavo).map_err(ValsiError::Phonotactics)?;
+        }
+        return Ok(if cmavo.len() == 1 {
+            ValsiKind::Cmavo
+        } else {
+            ValsiKind::CmavoCluster(cmavo)
+        });
+    }
+
+    if rafsi_shape(word).is_ok_and(|shape| shape.is_gismu()) {
+        return Ok(ValsiKind::Gismu);
+    }
+    if is_lujvo_shape(word) {
+        return jvokaha(word)
+            .map(ValsiKind::Lujvo)
+            .map_err(|e| ValsiError::Lujvo(e.to_string()));
+    }
+    fuhivla_stage(word).map(ValsiKind::Fuhivla).map_err(ValsiError::Fuhivla)
+}
+
+/// Split a word without consonant clusters into cmavo, each starting at a
+/// consonant
+fn split_cmavo(word: &str) -> Vec<String> {
+    let mut cmavo: Vec<String> = Vec::new();
+    for c in word.chars() {
+        match cmavo.last_mut() {
+            Some(last) if !is_consonant(c) => last.push(c),
+            _ => cmavo.push(c.to_string()),
+        }
+    }
+    cmavo
+}
+
+/// Word found in running text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Word in lower case, without pauses
+    pub text: String,
+    /// Byte range of the word in the input text
+    pub span: Range<usize>,
+    pub kind: ValsiKind,
+}
+
+/// Split running text into words. Whitespace and `.` are pauses; within a
+/// stretch without pauses, words ending in a consonant are cmevla (after a
+/// leading `la`, `lai` or `doi`), leading cmavo are split off before the
+/// first brivla that classifies as valid, and a capitalized stressed syllable
+/// ends a brivla one syllable later
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chunk: Vec<(usize, char)> = Vec::new();
+    for (offset, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if c.is_whitespace() || c == '.' {
+            tokenize_chunk(&chunk, text.len(), &mut tokens);
+            chunk.clear();
+        } else {
+            chunk.push((offset, c));
+        }
+    }
+    tokens
+}
+
+/// Letters of a pause-free stretch with their byte offsets
+struct Chunk<'a> {
+    letters: &'a [(usize, char)],
+    text_len: usize,
+}
+
+impl Chunk<'_> {
+    fn lowercase(&self, range: Range<usize>) -> String {
+        self.letters[range].iter().map(|&(_, c)| c.to_ascii_lowercase()).collect()
+    }
+
+    fn span(&self, range: &Range<usize>) -> Range<usize> {
+        let end = self.letters.get(range.end).map_or_else(
+            || self.letters.last().map_or(self.text_len, |&(offset, c)| offset + c.len_utf8()),
+            |&(offset, _)| offset,
+        );
+        self.letters[range.start].0..end
+    }
+
+    fn token(&self, range: Range<usize>, kind: ValsiKind) -> Token {
+        Token {
+            text: self.lowercase(range.clone()),
+            span: self.span(&range),
+            kind,
+        }
+    }
+}
+
+fn tokenize_chunk(letters: &[(usize, char)], text_len: usize, tokens: &mut Vec<Token>) {
+    if letters.is_empty() {
+        return;
+    }
+    let chunk = Chunk { letters, text_len };
+    let word = chunk.lowercase(0..letters.len());
+
+    if word.ends_with(is_consonant) || word.contains(',') {
+        if let Some(split) = cmevla_split(&word) {
+            push_cmavo(&chunk, 0..split, tokens);
+            tokens.push(chunk.token(split..letters.len(), ValsiKind::Cmevla));
+        } else {
+            tokens.push(chunk.token(0..letters.len(), classify(&word)));
+        }
+        return;
+    }
+
+    let chars: Vec<char> = word.chars().collect();
+    let mut start = 0;
+    while start < chars.len() {
+        let Some(cluster) = (start..chars.len().saturating_sub(1))
+            .find(|&i| is_consonant(chars[i]) && is_consonant(chars[i + 1]))
+        else {
+            push_cmavo(&chunk, start..chars.len(), tokens);
+            return;
+        };
+
+        let brivla = (start..=cluster)
+            .filter(|&i| is_consonant(chars[i]))
+            .find_map(|i| {
+                let end = stress_end(letters, &chars, i).unwrap_or(chars.len());
+                let kind = classify(&chars[i..end].iter().collect::<String>());
+                kind.is_valid().then_some((i, end, kind))
+            });
+        match brivla {
+            Some((brivla_start, end, kind)) => {
+                push_cmavo(&chunk, start..brivla_start, tokens);
+                tokens.push(chunk.token(brivla_start..end, kind));
+                start = end;
+            }
+            None => {
+                let rest: String = chars[start..].iter().collect();
+                tokens.push(chunk.token(start..chars.len(), classify(&rest)));
+                return;
+            }
+        }
+    }
+}
+
+/// Start of the cmevla in a word ending in a consonant: 0 when the whole
+/// word is a valid cmevla, or the end of a leading `la`, `lai` or `doi`
+fn cmevla_split(word: &str) -> Option<usize> {
+    if check_cmevla(word).is_ok() {
+        return Some(0);
+    }
+    la_lai_doi_positions(word, LaLaiDoiRule::AfterVowel)
+        .flat_map(|i| [i + 2, i + 3])
+        .filter(|&end| end < word.len() && word.is_char_boundary(end))
+        .find(|&end| {
+            matches!(classify(&word[..end]), ValsiKind::Cmavo | ValsiKind::CmavoCluster(_))
+                && check_cmevla(&word[end..]).is_ok()
+        })
+}
+
+/// End of a brivla starting at `start` whose stress is marked with capitals:
+/// the end of the vowels of the syllable after the stressed one
+fn stress_end(letters: &[(usize, char)], chars: &[char], start: usize) -> Option<usize> {
+    let stressed = (start..chars.len()).find(|&i| letters[i].1.is_uppercase() && is_vowel(chars[i]))?;
+    let mut i = stressed;
+    while i < chars.len() && is_vowel(chars[i]) {
+        i += 1;
+    }
+    while i < chars.len() && !is_vowel(chars[i]) {
+        i += 1;
+    }
+    let next_syllable = i;
+    while i < chars.len() && is_vowel(chars[i]) {
+        i += 1;
+    }
+    (i > next_syllable).then_some(i)
+}
+
+fn push_cmavo(chunk: &Chunk, range: Range<usize>, tokens: &mut Vec<Token>) {
+    if range.is_empty() {
+        return;
+    }
+    let mut start = range.start;
+    for cmavo in split_cmavo(&chunk.lowercase(range)) {
+        let end = start + cmavo.chars().count();
+        tokens.push(chunk.token(start..end, classify(&cmavo)));
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn tokenize_keeps_byte_spans() {
+        let text = "la .alis. klama";
+        let tokens = tokenize(text);
+        assert_eq!(words(&tokens), ["la", "alis", "klama"]);
+        let spans: Vec<_> = tokens.iter().map(|t| t.span.clone()).collect();
+        assert_eq!(spans, [0..2, 4..8, 10..15]);
+        assert_eq!(&text[tokens[1].span.clone()], "alis");
+        assert_eq!(tokens[0].kind, ValsiKind::Cmavo);
+        assert_eq!(tokens[1].kind, ValsiKind::Cmevla);
+        assert_eq!(tokens[2].kind, ValsiKind::Gismu);
+    }
+
+    #[test]
+    fn tokenize_splits_unspaced_text() {
+        assert_eq!(words(&tokenize("lobroda")), ["lo", "broda"]);
+        assert_eq!(words(&tokenize("KLAmaGASnu")), ["klama", "gasnu"]);
+        assert_eq!(words(&tokenize("ladjan")), ["la", "djan"]);
+        let spans: Vec<_> = tokenize("mi ĉu").into_iter().map(|t| t.span).collect();
+        assert_eq!(spans, [0..2, 3..6]);
+    }
+
+    #[test]
+    fn classify_rejects_invalid_words() {
+        assert_eq!(classify("kalga'u"), ValsiKind::Lujvo(vec!["kal".to_string(), "ga'u".to_string()]));
+        assert_eq!(classify(""), ValsiKind::Invalid(ValsiError::Empty));
+        assert_eq!(
+            classify("bay"),
+            ValsiKind::Invalid(ValsiError::Phonotactics(PhonotacticError::MisplacedY(2)))
+        );
+        assert!(matches!(classify("ladjan"), ValsiKind::Invalid(ValsiError::Cmevla(CmevlaError::LaLaiDoi))));
+    }
+}
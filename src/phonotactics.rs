@@ -0,0 +1,337 @@
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::libs::config::{
+    C, DIPHTHONGS, FORBIDDEN_CC, FORBIDDEN_CCC, SIBILANT, UNVOICED, V, VALID_CC_INITIALS, VOICED,
+};
+
+static VALID_CC_INITIALS_SET: Lazy<HashSet<&'static str>> =
+    Lazy::new(|| VALID_CC_INITIALS.iter().cloned().collect());
+
+static FORBIDDEN_CC_SET: Lazy<HashSet<&'static str>> =
+    Lazy::new(|| FORBIDDEN_CC.iter().cloned().collect());
+
+static FORBIDDEN_CCC_SET: Lazy<HashSet<&'static str>> =
+    Lazy::new(|| FORBIDDEN_CCC.iter().cloned().collect());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhonotacticError {
+    InvalidLetter(char),
+    InvalidInitialPair(String),
+    ForbiddenPair(String),
+    ForbiddenTriple(String),
+    InvalidVowelCluster(String),
+    MisplacedApostrophe(usize),
+    MisplacedY(usize),
+}
+
+impl fmt::Display for PhonotacticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhonotacticError::InvalidLetter(c) => write!(f, "'{}' is not a Lojban letter", c),
+            PhonotacticError::InvalidInitialPair(cc) => {
+                write!(f, "'{}' cannot start a word", cc)
+            }
+            PhonotacticError::ForbiddenPair(cc) => {
+                write!(f, "consonant pair '{}' is not permissible", cc)
+            }
+            PhonotacticError::ForbiddenTriple(ccc) => {
+                write!(f, "consonant triple '{}' is not permissible", ccc)
+            }
+            PhonotacticError::InvalidVowelCluster(vv) => {
+                write!(f, "vowel cluster '{}' is not permissible", vv)
+            }
+            PhonotacticError::MisplacedApostrophe(i) => {
+                write!(f, "apostrophe at position {} must stand between vowels", i)
+            }
+            PhonotacticError::MisplacedY(i) => {
+                write!(f, "'y' at position {} must not touch another vowel", i)
+            }
+        }
+    }
+}
+
+impl Error for PhonotacticError {}
+
+#[inline]
+pub fn is_consonant(c: char) -> bool {
+    C.contains(c)
+}
+
+#[inline]
+pub fn is_vowel(c: char) -> bool {
+    V.contains(c)
+}
+
+/// Pair that may begin a word, e.g. `bl` or `st`
+pub fn is_valid_initial_pair(c1: char, c2: char) -> bool {
+    VALID_CC_INITIALS_SET.contains(format!("{}{}", c1, c2).as_str())
+}
+
+/// Pair that may stand between vowels inside a word
+pub fn is_permissible_pair(c1: char, c2: char) -> bool {
+    let voiced = |c: char| VOICED.contains(c);
+    let unvoiced = |c: char| UNVOICED.contains(c);
+    let sibilant = |c: char| SIBILANT.contains(c);
+
+    !(c1 == c2
+        || (voiced(c1) && unvoiced(c2))
+        || (unvoiced(c1) && voiced(c2))
+        || (sibilant(c1) && sibilant(c2))
+        || FORBIDDEN_CC_SET.contains(format!("{}{}", c1, c2).as_str()))
+}
+
+pub fn is_forbidden_triple(c1: char, c2: char, c3: char) -> bool {
+    FORBIDDEN_CCC_SET.contains(format!("{}{}{}", c1, c2, c3).as_str())
+}
+
+/// Three consonants inside a word: a permissible pair followed by an initial
+/// pair, other than `ndj`, `ndz`, `ntc` and `nts`
+pub fn is_valid_triple(c1: char, c2: char, c3: char) -> bool {
+    is_permissible_pair(c1, c2) && is_valid_initial_pair(c2, c3) && !is_forbidden_triple(c1, c2, c3)
+}
+
+/// Where a consonant pair may stand, from most to least restricted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PairKind {
+    /// Never allowed, e.g. `mz`
+    Forbidden,
+    /// Allowed between vowels only, e.g. `rk`
+    Medial,
+    /// Allowed anywhere, including at the start of a word, e.g. `kl`
+    Initial,
+}
+
+pub fn pair_kind(c1: char, c2: char) -> PairKind {
+    if is_valid_initial_pair(c1, c2) {
+        PairKind::Initial
+    } else if is_permissible_pair(c1, c2) {
+        PairKind::Medial
+    } else {
+        PairKind::Forbidden
+    }
+}
+
+/// All pairs that may begin a word
+pub fn valid_initial_pairs() -> impl Iterator<Item = &'static str> {
+    VALID_CC_INITIALS.iter().copied()
+}
+
+/// One of `ai`, `ei`, `oi`, `au`
+pub fn is_diphthong(v1: char, v2: char) -> bool {
+    DIPHTHONGS.iter().any(|d| d.starts_with(v1) && d.ends_with(v2))
+}
+
+/// Two vowels written together: a diphthong or an `i`/`u` glide before a vowel
+pub fn is_valid_vowel_pair(v1: char, v2: char) -> bool {
+    is_diphthong(v1, v2) || matches!(v1, 'i' | 'u')
+}
+
+/// Keep Lojban letters only and merge doubled consonants, e.g. for a foreign
+/// word written in Lojban letters
+pub fn normalize_letters(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.to_lowercase().chars().filter(|&c| is_consonant(c) || is_vowel(c)) {
+        if is_consonant(c) && out.ends_with(c) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Check a word against Lojban consonant cluster, vowel cluster, apostrophe
+/// and `y` rules
+pub fn check_word(word: &str) -> Result<(), PhonotacticError> {
+    let chars: Vec<char> = word.chars().collect();
+
+    if let Some(&c) = chars
+        .iter()
+        .find(|&&c| !is_consonant(c) && !is_vowel(c) && c != 'y' && c != '\'')
+    {
+        return Err(PhonotacticError::InvalidLetter(c));
+    }
+
+    let class = |c: char| (is_consonant(c), is_vowel(c));
+    let mut i = 0;
+    while i < chars.len() {
+        let run = chars[i..]
+            .iter()
+            .take_while(|&&c| class(c) == class(chars[i]))
+            .count();
+        let cluster = &chars[i..i + run];
+        if is_consonant(chars[i]) {
+            check_consonant_cluster(cluster, i == 0)?;
+        } else if is_vowel(chars[i]) {
+            check_vowel_cluster(cluster)?;
+        }
+        i += run;
+    }
+
+    for (i, &c) in chars.iter().enumerate() {
+        let before = i.checked_sub(1).map(|j| chars[j]);
+        let after = chars.get(i + 1).copied();
+        match c {
+            '\'' => {
+                let is_vowel_or_y = |c: Option<char>| c.is_some_and(|c| is_vowel(c) || c == 'y');
+                if !is_vowel_or_y(before) || !is_vowel_or_y(after) {
+                    return Err(PhonotacticError::MisplacedApostrophe(i));
+                }
+            }
+            'y' => {
+                let touches_vowel = |c: Option<char>| c.is_some_and(|c| is_vowel(c) || c == 'y');
+                if touches_vowel(before) || touches_vowel(after) {
+                    return Err(PhonotacticError::MisplacedY(i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Word classes with their own vowel, apostrophe and `y` rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordClass {
+    /// `y` only as `y`, `y'y` or a consonant followed by `y`
+    Cmavo,
+    /// No `y`; two vowels form a diphthong or are split by an apostrophe,
+    /// which only occurs in CV'V
+    Rafsi,
+    /// Gismu and lujvo: `y` is a hyphen between consonants, never final
+    Brivla,
+    /// No `y` at all
+    Fuhivla,
+    Cmevla,
+}
+
+/// [`check_word`] followed by the vowel, apostrophe and `y` rules of a word class
+pub fn check_word_class(word: &str, class: WordClass) -> Result<(), PhonotacticError> {
+    check_word(word)?;
+    let chars: Vec<char> = word.chars().collect();
+    let y_at = |allowed: &dyn Fn(usize) -> bool| {
+        chars
+            .iter()
+            .enumerate()
+            .find(|&(i, &c)| c == 'y' && !allowed(i))
+            .map(|(i, _)| PhonotacticError::MisplacedY(i))
+    };
+
+    let misplaced_y = match class {
+        WordClass::Cmavo => {
+            let lerfu = matches!(chars.as_slice(), ['y'] | ['y', '\'', 'y'])
+                || matches!(chars.as_slice(), [c, 'y'] if is_consonant(*c));
+            y_at(&|_| lerfu)
+        }
+        WordClass::Rafsi | WordClass::Fuhivla => y_at(&|_| false),
+        WordClass::Brivla => y_at(&|i| i + 1 < chars.len()),
+        WordClass::Cmevla => None,
+    };
+    if let Some(e) = misplaced_y {
+        return Err(e);
+    }
+
+    if class == WordClass::Rafsi {
+        if let Some(pair) = chars
+            .windows(2)
+            .find(|p| is_vowel(p[0]) && is_vowel(p[1]) && !is_diphthong(p[0], p[1]))
+        {
+            return Err(PhonotacticError::InvalidVowelCluster(pair.iter().collect()));
+        }
+        let cv_apostrophe = chars.len() == 4 && is_consonant(chars[0]) && chars[2] == '\'';
+        if let Some(i) = chars.iter().position(|&c| c == '\'').filter(|_| !cv_apostrophe) {
+            return Err(PhonotacticError::MisplacedApostrophe(i));
+        }
+    }
+    Ok(())
+}
+
+fn check_consonant_cluster(cluster: &[char], word_initial: bool) -> Result<(), PhonotacticError> {
+    let as_string = |letters: &[char]| letters.iter().collect::<String>();
+
+    for (j, pair) in cluster.windows(2).enumerate() {
+        if !is_permissible_pair(pair[0], pair[1]) {
+            return Err(PhonotacticError::ForbiddenPair(as_string(pair)));
+        }
+        // Initial clusters consist of initial pairs only; inside a word every
+        // pair after the first must be able to start a syllable
+        if (word_initial || j > 0) && !is_valid_initial_pair(pair[0], pair[1]) {
+            return Err(if word_initial {
+                PhonotacticError::InvalidInitialPair(as_string(pair))
+            } else {
+                PhonotacticError::ForbiddenTriple(as_string(&cluster[j - 1..j + 2]))
+            });
+        }
+    }
+    for triple in cluster.windows(3) {
+        if is_forbidden_triple(triple[0], triple[1], triple[2]) {
+            return Err(PhonotacticError::ForbiddenTriple(as_string(triple)));
+        }
+    }
+    Ok(())
+}
+
+fn check_vowel_cluster(cluster: &[char]) -> Result<(), PhonotacticError> {
+    let valid = match cluster {
+        [_] => true,
+        [v1, v2] => is_valid_vowel_pair(*v1, *v2),
+        // A glide may precede a diphthong, as in `uai`
+        [g, v1, v2] => matches!(g, 'i' | 'u') && is_diphthong(*v1, *v2),
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(PhonotacticError::InvalidVowelCluster(cluster.iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmavo_accept_y_only_in_letter_names() {
+        assert!(check_word_class("y'y", WordClass::Cmavo).is_ok());
+        assert!(check_word_class("ny", WordClass::Cmavo).is_ok());
+        assert!(check_word_class("y", WordClass::Cmavo).is_ok());
+        assert_eq!(check_word_class("ya", WordClass::Cmavo), Err(PhonotacticError::MisplacedY(0)));
+        assert_eq!(check_word_class("ba'y", WordClass::Cmavo), Err(PhonotacticError::MisplacedY(3)));
+    }
+
+    #[test]
+    fn rafsi_need_diphthongs_and_cv_apostrophe_v() {
+        assert!(check_word_class("sai", WordClass::Rafsi).is_ok());
+        assert!(check_word_class("ga'u", WordClass::Rafsi).is_ok());
+        assert_eq!(
+            check_word_class("xia", WordClass::Rafsi),
+            Err(PhonotacticError::InvalidVowelCluster("ia".to_string()))
+        );
+        assert_eq!(check_word_class("a'ai", WordClass::Rafsi), Err(PhonotacticError::MisplacedApostrophe(1)));
+        assert_eq!(check_word_class("bly", WordClass::Rafsi), Err(PhonotacticError::MisplacedY(2)));
+    }
+
+    #[test]
+    fn brivla_and_fuhivla_y() {
+        assert!(check_word_class("lojbyglico", WordClass::Brivla).is_ok());
+        assert_eq!(check_word_class("klamy", WordClass::Brivla), Err(PhonotacticError::MisplacedY(4)));
+        assert_eq!(check_word_class("spatyti", WordClass::Fuhivla), Err(PhonotacticError::MisplacedY(4)));
+        assert!(check_word_class("djan", WordClass::Cmevla).is_ok());
+    }
+
+    #[test]
+    fn class_rules_run_after_check_word() {
+        assert_eq!(
+            check_word_class("mlatu'", WordClass::Brivla),
+            Err(PhonotacticError::MisplacedApostrophe(5))
+        );
+        assert_eq!(
+            check_word_class("rkoi", WordClass::Cmavo),
+            Err(PhonotacticError::InvalidInitialPair("rk".to_string()))
+        );
+        assert_eq!(check_word_class("kaq", WordClass::Cmevla), Err(PhonotacticError::InvalidLetter('q')));
+    }
+}
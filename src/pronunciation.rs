@@ -0,0 +1,231 @@
+use serde::Serialize;
+
+use crate::phonotactics::{is_consonant, is_diphthong, is_valid_initial_pair, is_vowel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Nucleus {
+    /// Single vowel or diphthong
+    Vowel,
+    /// `y`, which never takes the stress
+    Schwa,
+    /// `l`, `m`, `n` or `r` between consonants that cannot join either
+    /// neighbour, which never takes the stress
+    Consonant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Syllable {
+    pub text: String,
+    pub nucleus: Nucleus,
+}
+
+/// Syllables, stress and IPA transcription of a word
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Pronunciation {
+    pub word: String,
+    pub syllables: Vec<Syllable>,
+    /// Index of the stressed syllable, `None` for words with fewer than two
+    /// syllables that can take the stress
+    pub stress: Option<usize>,
+    /// Word with the stressed syllable in capitals, e.g. `KLAma`
+    pub stressed: String,
+    pub ipa: String,
+}
+
+impl Pronunciation {
+    /// `kla.ma KLAma /ˈkla.ma/`
+    pub fn summary(&self) -> String {
+        let syllables: Vec<&str> = self.syllables.iter().map(|s| s.text.as_str()).collect();
+        format!("{} {} /{}/", syllables.join("."), self.stressed, self.ipa)
+    }
+}
+
+pub fn pronounce(word: &str) -> Pronunciation {
+    let word = word.trim().trim_matches('.').to_lowercase();
+    let syllables = syllabify(&word);
+    let stress = stress_index(&syllables);
+    let stressed = syllables
+        .iter()
+        .enumerate()
+        .map(|(i, s)| if Some(i) == stress { s.text.to_uppercase() } else { s.text.clone() })
+        .collect();
+    let ipa = syllables
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let separator = match (Some(i) == stress, i) {
+                (true, _) => "ˈ",
+                (false, 0) => "",
+                (false, _) => ".",
+            };
+            format!("{}{}", separator, syllable_ipa(s))
+        })
+        .collect();
+
+    Pronunciation {
+        word,
+        syllables,
+        stress,
+        stressed,
+        ipa,
+    }
+}
+
+/// Split a word into syllables. Each nucleus takes the consonant, glide or
+/// apostrophe right before it as onset; a word-initial cluster opens the
+/// first syllable and a word-final cluster closes the last
+pub fn syllabify(word: &str) -> Vec<Syllable> {
+    let chars: Vec<char> = word.chars().filter(|&c| c != ',').collect();
+    let nuclei = find_nuclei(&chars);
+    if nuclei.is_empty() {
+        return if chars.is_empty() {
+            Vec::new()
+        } else {
+            vec![Syllable {
+                text: chars.iter().collect(),
+                nucleus: Nucleus::Consonant,
+            }]
+        };
+    }
+
+    let mut syllables = Vec::with_capacity(nuclei.len());
+    let mut start = 0;
+    for (n, &(_, nucleus_end, nucleus)) in nuclei.iter().enumerate() {
+        let end = match nuclei.get(n + 1) {
+            // Syllabic consonants take no onset
+            Some(&(next_start, _, Nucleus::Consonant)) => next_start,
+            Some(&(next_start, _, _)) => onset_start(&chars, nucleus_end, next_start),
+            None => chars.len(),
+        };
+        syllables.push(Syllable {
+            text: chars[start..end].iter().collect(),
+            nucleus,
+        });
+        start = end;
+    }
+    syllables
+}
+
+/// Penultimate syllable among those with a vowel nucleus
+pub fn stress_index(syllables: &[Syllable]) -> Option<usize> {
+    let stressable: Vec<usize> = syllables
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.nucleus == Nucleus::Vowel)
+        .map(|(i, _)| i)
+        .collect();
+    (stressable.len() >= 2).then(|| stressable[stressable.len() - 2])
+}
+
+/// IPA of one syllable, without stress marks or separators
+pub fn syllable_ipa(syllable: &Syllable) -> String {
+    let chars: Vec<char> = syllable.text.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let next_is_vowel = chars.get(i + 1).is_some_and(|&n| is_vowel(n));
+        let after_vowel = i > 0 && is_vowel(chars[i - 1]);
+        let glide = (c == 'i' || c == 'u') && (next_is_vowel || (after_vowel && is_diphthong(chars[i - 1], c)));
+        match c {
+            'i' if glide => out.push('j'),
+            'u' if glide => out.push('w'),
+            _ => out.push_str(letter_ipa(c)),
+        }
+        if syllable.nucleus == Nucleus::Consonant && "lmnr".contains(c) {
+            let is_nucleus = chars
+                .iter()
+                .rposition(|&l| "lmnr".contains(l))
+                .is_some_and(|last| last == i);
+            if is_nucleus {
+                out.push('\u{0329}');
+            }
+        }
+    }
+    out
+}
+
+fn letter_ipa(c: char) -> &'static str {
+    match c {
+        'a' => "a",
+        'e' => "ɛ",
+        'i' => "i",
+        'o' => "ɔ",
+        'u' => "u",
+        'y' => "ə",
+        'c' => "ʃ",
+        'j' => "ʒ",
+        'g' => "ɡ",
+        '\'' => "h",
+        'b' => "b",
+        'd' => "d",
+        'f' => "f",
+        'k' => "k",
+        'l' => "l",
+        'm' => "m",
+        'n' => "n",
+        'p' => "p",
+        'r' => "r",
+        's' => "s",
+        't' => "t",
+        'v' => "v",
+        'x' => "x",
+        'z' => "z",
+        _ => "",
+    }
+}
+
+/// Nucleus spans as `(start, end, kind)`, end exclusive
+fn find_nuclei(chars: &[char]) -> Vec<(usize, usize, Nucleus)> {
+    let mut nuclei: Vec<(usize, usize, Nucleus)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == 'y' {
+            nuclei.push((i, i + 1, Nucleus::Schwa));
+        } else if is_vowel(c) {
+            // `i` and `u` before another vowel are glides
+            if (c == 'i' || c == 'u') && next.is_some_and(is_vowel) {
+                i += 1;
+                continue;
+            }
+            let diphthong = next.is_some_and(|n| is_diphthong(c, n)) && !chars.get(i + 2).is_some_and(|&n| is_vowel(n));
+            let end = if diphthong {
+                i + 2
+            } else {
+                i + 1
+            };
+            nuclei.push((i, end, Nucleus::Vowel));
+            i = end;
+            continue;
+        } else if is_syllabic_consonant(chars, i) {
+            nuclei.push((i, i + 1, Nucleus::Consonant));
+        }
+        i += 1;
+    }
+    nuclei
+}
+
+fn is_syllabic_consonant(chars: &[char], i: usize) -> bool {
+    let (Some(&before), Some(&after)) = (i.checked_sub(1).and_then(|j| chars.get(j)), chars.get(i + 1)) else {
+        return false;
+    };
+    "lmnr".contains(chars[i])
+        && is_consonant(before)
+        && is_consonant(after)
+        && !is_valid_initial_pair(before, chars[i])
+        && !is_valid_initial_pair(chars[i], after)
+}
+
+/// Start of the next syllable: the last consonant before the next nucleus,
+/// together with any glide or apostrophe
+fn onset_start(chars: &[char], nucleus_end: usize, next_start: usize) -> usize {
+    let mut start = next_start;
+    while start > nucleus_end && matches!(chars[start - 1], 'i' | 'u' | '\'') {
+        start -= 1;
+    }
+    if start > nucleus_end && is_consonant(chars[start - 1]) {
+        start -= 1;
+    }
+    start
+}
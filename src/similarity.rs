@@ -0,0 +1,374 @@
+use std::fmt;
+
+use crate::libs::config::SIMILARITIES;
+use crate::phonotactics::is_consonant;
+
+/// Names accepted by [`metric_from_name`], default first
+pub const METRIC_NAMES: [&str; 4] = ["lcs", "feature", "skeleton", "position"];
+
+/// How closely a gismu candidate resembles one source word
+pub trait SimilarityMetric: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Similarity of `candidate` to `source`, normalized so that a full
+    /// match of the source word scores 1
+    fn similarity(&self, candidate: &str, source: &SourceWord) -> f32;
+}
+
+/// Longest common subsequence, where two common letters only count when they
+/// form a dyad found in the source word; the original gismu-making metric
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LcsDyadMetric;
+
+impl SimilarityMetric for LcsDyadMetric {
+    fn name(&self) -> &'static str {
+        "lcs"
+    }
+
+    fn similarity(&self, candidate: &str, source: &SourceWord) -> f32 {
+        self.raw_score(candidate, source) / source.len() as f32
+    }
+}
+
+impl LcsDyadMetric {
+    /// Score before dividing by the length of the source word: the LCS
+    /// length, or 0 when it is below 2 or is 2 without a shared dyad
+    pub fn raw_score(&self, candidate: &str, source: &SourceWord) -> f32 {
+        match source.lcs_length(candidate) {
+            0 | 1 => 0.0,
+            2 if source.has_dyad(candidate) => 2.0,
+            2 => 0.0,
+            n => n as f32,
+        }
+    }
+}
+
+/// Edit distance where substituting a letter listed as similar in
+/// `SIMILARITIES` (voicing or place of articulation) costs half
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureEditMetric {
+    pub similar_cost: f32,
+}
+
+impl Default for FeatureEditMetric {
+    fn default() -> Self {
+        Self { similar_cost: 0.5 }
+    }
+}
+
+impl FeatureEditMetric {
+    fn substitution_cost(&self, a: char, b: char) -> f32 {
+        if a == b {
+            0.0
+        } else if are_similar(a, b) {
+            self.similar_cost
+        } else {
+            1.0
+        }
+    }
+}
+
+impl SimilarityMetric for FeatureEditMetric {
+    fn name(&self) -> &'static str {
+        "feature"
+    }
+
+    fn similarity(&self, candidate: &str, source: &SourceWord) -> f32 {
+        let word: Vec<char> = source.as_str().chars().collect();
+        let mut previous: Vec<f32> = (0..=word.len()).map(|j| j as f32).collect();
+        let mut current = vec![0.0; word.len() + 1];
+
+        for (i, c) in candidate.chars().enumerate() {
+            current[0] = (i + 1) as f32;
+            for (j, &w) in word.iter().enumerate() {
+                current[j + 1] = (previous[j] + self.substitution_cost(c, w))
+                    .min(previous[j + 1] + 1.0)
+                    .min(current[j] + 1.0);
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+
+        let longest = candidate.chars().count().max(word.len());
+        if longest == 0 {
+            0.0
+        } else {
+            1.0 - previous[word.len()] / longest as f32
+        }
+    }
+}
+
+/// Longest common subsequence of the consonants only, ignoring vowels
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsonantSkeletonMetric;
+
+impl SimilarityMetric for ConsonantSkeletonMetric {
+    fn name(&self) -> &'static str {
+        "skeleton"
+    }
+
+    fn similarity(&self, candidate: &str, source: &SourceWord) -> f32 {
+        let skeleton = source.skeleton();
+        if skeleton.is_empty() {
+            return 0.0;
+        }
+        let candidate_skeleton: String = candidate.chars().filter(|&c| is_consonant(c)).collect();
+        source.skeleton_lcs_length(&candidate_skeleton) as f32 / skeleton.len() as f32
+    }
+}
+
+/// Longest common subsequence where a matched letter is worth less the later
+/// it appears in the source word, favoring word-initial agreement
+#[derive(Debug, Clone, Copy)]
+pub struct PositionWeightedMetric {
+    /// Worth of a match relative to a match one position earlier
+    pub decay: f32,
+}
+
+impl Default for PositionWeightedMetric {
+    fn default() -> Self {
+        Self { decay: 0.8 }
+    }
+}
+
+impl SimilarityMetric for PositionWeightedMetric {
+    fn name(&self) -> &'static str {
+        "position"
+    }
+
+    fn similarity(&self, candidate: &str, source: &SourceWord) -> f32 {
+        let (a, b) = (candidate.as_bytes(), source.as_str().as_bytes());
+        let worth: Vec<f32> = (0..b.len()).map(|j| self.decay.powi(j as i32)).collect();
+        let total: f32 = worth.iter().sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        let mut previous = vec![0.0f32; b.len() + 1];
+        let mut current = vec![0.0f32; b.len() + 1];
+        for &ca in a {
+            for (j, &cb) in b.iter().enumerate() {
+                current[j + 1] = if ca == cb {
+                    previous[j] + worth[j]
+                } else {
+                    previous[j + 1].max(current[j])
+                };
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+        previous[b.len()] / total
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownMetric(pub String);
+
+impl fmt::Display for UnknownMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unknown similarity metric '{}', expected one of: {}",
+            self.0,
+            METRIC_NAMES.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownMetric {}
+
+/// Metric with default settings for one of [`METRIC_NAMES`]
+pub fn metric_from_name(name: &str) -> Result<Box<dyn SimilarityMetric>, UnknownMetric> {
+    match name.trim().to_lowercase().as_str() {
+        "lcs" => Ok(Box::new(LcsDyadMetric)),
+        "feature" => Ok(Box::new(FeatureEditMetric::default())),
+        "skeleton" => Ok(Box::new(ConsonantSkeletonMetric)),
+        "position" => Ok(Box::new(PositionWeightedMetric::default())),
+        other => Err(UnknownMetric(other.to_string())),
+    }
+}
+
+fn are_similar(a: char, b: char) -> bool {
+    SIMILARITIES
+        .iter()
+        .any(|&(letter, similar)| letter == a && similar.contains(b))
+}
+
+/// One longest common subsequence of two words as pairs of byte positions
+pub fn lcs_alignment(a: &str, b: &str) -> Vec<(usize, usize)> {
+    let (a_bytes, b_bytes) = (a.as_bytes(), b.as_bytes());
+    let (m, n) = (a_bytes.len(), b_bytes.len());
+    let mut table = vec![vec![0usize; n + 1]; m + 1];
+
+    for i in (0..m).rev() {
+        for j in (0..n).rev() {
+            table[i][j] = if a_bytes[i] == b_bytes[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut alignment = Vec::with_capacity(table[0][0]);
+    let (mut i, mut j) = (0, 0);
+    while i < m && j < n {
+        if a_bytes[i] == b_bytes[j] {
+            alignment.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    alignment
+}
+
+/// Bit `i` of `masks[b]` is set when byte `i` of the word is `b`; `None` for
+/// words longer than 64 bytes
+fn match_masks(word: &str) -> Option<Box<[u64; 256]>> {
+    let bytes = word.as_bytes();
+    (bytes.len() <= 64).then(|| {
+        let mut masks = Box::new([0u64; 256]);
+        for (i, &b) in bytes.iter().enumerate() {
+            masks[b as usize] |= 1 << i;
+        }
+        masks
+    })
+}
+
+/// LCS length of `candidate` with `word`, computed a machine word at a time
+/// (Allison–Dix, Hyyrö) when `masks` are available
+fn masked_lcs_length(masks: Option<&[u64; 256]>, word: &str, candidate: &str) -> usize {
+    let Some(masks) = masks else {
+        return lcs_alignment(candidate, word).len();
+    };
+    let live = if word.len() == 64 { u64::MAX } else { (1 << word.len()) - 1 };
+
+    let v = candidate.bytes().fold(live, |v, b| {
+        let u = v & masks[b as usize];
+        v.wrapping_add(u) | (v - u)
+    });
+    (!v & live).count_ones() as usize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DyadKind {
+    /// Letters of the dyad are one letter apart in the source word
+    Gapped,
+    /// Letters of the dyad are adjacent in the source word
+    Adjacent,
+}
+
+/// Dyad that earned full credit for an LCS of two letters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DyadMatch {
+    pub dyad: String,
+    pub kind: DyadKind,
+}
+
+/// Source word prepared once per run: bit masks for a bit-parallel LCS and
+/// the letter pairs looked up by the dyad rule
+#[derive(Debug, Clone)]
+pub struct SourceWord {
+    text: String,
+    /// Masks for the bit-parallel LCS; see [`match_masks`]
+    match_masks: Option<Box<[u64; 256]>>,
+    /// Letter pairs one position apart, `w[i]` and `w[i + 2]`
+    gapped_dyads: Vec<[u8; 2]>,
+    adjacent_dyads: Vec<[u8; 2]>,
+    skeleton: String,
+    skeleton_masks: Option<Box<[u64; 256]>>,
+}
+
+impl SourceWord {
+    pub fn new(word: &str) -> Self {
+        let chars: Vec<char> = word.chars().collect();
+        let skeleton: String = word.chars().filter(|&c| is_consonant(c)).collect();
+
+        Self {
+            text: word.to_string(),
+            match_masks: match_masks(word),
+            gapped_dyads: letter_pairs(&chars, 2),
+            adjacent_dyads: letter_pairs(&chars, 1),
+            skeleton_masks: match_masks(&skeleton),
+            skeleton,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Length in bytes, the denominator of normalized scores
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Consonants of the word in order
+    pub fn skeleton(&self) -> &str {
+        &self.skeleton
+    }
+
+    /// Length of the longest common subsequence with `candidate`
+    pub fn lcs_length(&self, candidate: &str) -> usize {
+        masked_lcs_length(self.match_masks.as_deref(), &self.text, candidate)
+    }
+
+    /// Length of the longest common subsequence of the skeleton with the
+    /// consonants of a candidate
+    pub fn skeleton_lcs_length(&self, candidate_skeleton: &str) -> usize {
+        masked_lcs_length(self.skeleton_masks.as_deref(), &self.skeleton, candidate_skeleton)
+    }
+
+    /// A two-letter LCS only counts when the candidate contains a dyad that
+    /// also occurs in the source word, either with one letter between
+    /// (ignoring the candidate's last dyad) or adjacent
+    pub fn find_dyad(&self, candidate: &str) -> Option<DyadMatch> {
+        let bytes = candidate.as_bytes();
+        let dyad_at = |i: usize| [bytes[i], bytes[i + 1]];
+
+        (0..bytes.len().saturating_sub(2))
+            .find(|&i| self.gapped_dyads.binary_search(&dyad_at(i)).is_ok())
+            .map(|i| (i, DyadKind::Gapped))
+            .or_else(|| {
+                (0..bytes.len().saturating_sub(1))
+                    .find(|&i| self.adjacent_dyads.binary_search(&dyad_at(i)).is_ok())
+                    .map(|i| (i, DyadKind::Adjacent))
+            })
+            .map(|(i, kind)| DyadMatch {
+                dyad: candidate[i..i + 2].to_string(),
+                kind,
+            })
+    }
+
+    pub fn has_dyad(&self, candidate: &str) -> bool {
+        let bytes = candidate.as_bytes();
+        let found_in = |dyads: &[[u8; 2]], end: usize| {
+            bytes
+                .windows(2)
+                .take(end)
+                .any(|pair| dyads.binary_search(&[pair[0], pair[1]]).is_ok())
+        };
+
+        found_in(&self.gapped_dyads, bytes.len().saturating_sub(2))
+            || found_in(&self.adjacent_dyads, bytes.len())
+    }
+}
+
+/// Sorted ASCII letter pairs `distance` positions apart
+fn letter_pairs(chars: &[char], distance: usize) -> Vec<[u8; 2]> {
+    let mut pairs: Vec<[u8; 2]> = chars
+        .iter()
+        .zip(chars.iter().skip(distance))
+        .filter(|(a, b)| a.is_ascii() && b.is_ascii())
+        .map(|(&a, &b)| [a as u8, b as u8])
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
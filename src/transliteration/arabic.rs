@@ -0,0 +1,76 @@
+use super::rewrite;
+
+static ARABIC_RULES: [(&str, &str); 46] = [
+    ("ا", "a"),
+    ("آ", "a"),
+    ("أ", "a"),
+    ("إ", "i"),
+    ("ب", "b"),
+    ("ت", "t"),
+    ("ث", "t"),
+    ("ج", "dj"),
+    ("ح", "'"),
+    ("خ", "x"),
+    ("د", "d"),
+    ("ذ", "d"),
+    ("ر", "r"),
+    ("ز", "z"),
+    ("س", "s"),
+    ("ش", "c"),
+    ("ص", "s"),
+    ("ض", "d"),
+    ("ط", "t"),
+    ("ظ", "z"),
+    ("ع", ""),
+    ("غ", "g"),
+    ("ف", "f"),
+    ("ق", "k"),
+    ("ك", "k"),
+    ("ل", "l"),
+    ("م", "m"),
+    ("ن", "n"),
+    ("ه", "'"),
+    ("و", "u"),
+    ("ي", "i"),
+    ("ى", "a"),
+    ("ء", ""),
+    ("ؤ", "u"),
+    ("ئ", "i"),
+    ("ة", "a"),
+    ("َ", "a"),
+    ("ِ", "i"),
+    ("ُ", "u"),
+    ("ْ", ""),
+    ("ّ", ""),
+    ("ً", "an"),
+    ("ٍ", "in"),
+    ("ٌ", "un"),
+    ("ـ", ""),
+    ("ه$", "a"),
+];
+
+/// Transliterate Arabic script into Lojban letters; long vowels written
+/// with alif, waw and ya are kept, short vowels only when marked
+pub fn transliterate(word: &str) -> String {
+    rewrite(word, &ARABIC_RULES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_arabic() {
+        for (arabic, lojban) in [
+            ("كتاب", "ktab"),
+            ("كِتَاب", "kitaab"),
+            ("مدرسة", "mdrsa"),
+            ("الله", "ala"),
+            ("خبز", "xbz"),
+            ("وَلَد", "ualad"),
+            ("شمس", "cms"),
+        ] {
+            assert_eq!(transliterate(arabic), lojban, "{}", arabic);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::rewrite;
+
+static ENGLISH_RULES: [(&str, &str); 36] = [
+    ("sh", "c"),
+    ("ch", "tc"),
+    ("tch", "tc"),
+    ("th", "t"),
+    ("ph", "f"),
+    ("ck", "k"),
+    ("gh", ""),
+    ("wh", "u"),
+    ("qu", "ku"),
+    ("q", "k"),
+    ("ng$", "n"),
+    ("tion", "cyn"),
+    ("sion", "jyn"),
+    ("ce", "se"),
+    ("ci", "si"),
+    ("cy", "si"),
+    ("c", "k"),
+    ("ge", "dje"),
+    ("gi", "dji"),
+    ("j", "dj"),
+    ("x", "ks"),
+    ("^x", "z"),
+    ("^y", "i"),
+    ("y", "i"),
+    ("w", "u"),
+    ("h", ""),
+    ("ee", "i"),
+    ("ea", "i"),
+    ("oo", "u"),
+    ("ou", "au"),
+    ("ow$", "o"),
+    ("oa", "o"),
+    ("ai", "ei"),
+    ("ay", "ei"),
+    ("igh", "ai"),
+    ("er$", "er"),
+];
+
+/// Transliterate English spelling into Lojban letters using common
+/// spelling-to-sound heuristics; irregular words need manual correction
+pub fn transliterate(word: &str) -> String {
+    let word = word.to_lowercase();
+    // A final silent `e` after a consonant is dropped unless it is the only vowel
+    let stem = word.strip_suffix('e').filter(|stem| {
+        stem.ends_with(|c: char| !"aeiouy".contains(c))
+            && stem.chars().any(|c| "aeiouy".contains(c))
+    });
+    rewrite(stem.unwrap_or(&word), &ENGLISH_RULES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_english() {
+        for (english, lojban) in [
+            ("church", "tcurtc"),
+            ("phone", "fon"),
+            ("nation", "nacyn"),
+            ("quick", "kuik"),
+            ("xenon", "zenon"),
+            ("box", "boks"),
+            ("king", "kin"),
+            ("see", "si"),
+            ("be", "be"),
+        ] {
+            assert_eq!(transliterate(english), lojban, "{}", english);
+        }
+    }
+}
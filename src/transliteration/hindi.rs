@@ -0,0 +1,221 @@
+/// Devanagari consonants; each carries an inherent `a` unless followed
+/// by a vowel sign or virama
+static CONSONANTS: [(char, &str); 34] = [
+    ('क', "k"),
+    ('ख', "k"),
+    ('ग', "g"),
+    ('घ', "g"),
+    ('ङ', "n"),
+    ('च', "tc"),
+    ('छ', "tc"),
+    ('ज', "dj"),
+    ('झ', "dj"),
+    ('ञ', "n"),
+    ('ट', "t"),
+    ('ठ', "t"),
+    ('ड', "d"),
+    ('ढ', "d"),
+    ('ण', "n"),
+    ('त', "t"),
+    ('थ', "t"),
+    ('द', "d"),
+    ('ध', "d"),
+    ('न', "n"),
+    ('प', "p"),
+    ('फ', "p"),
+    ('ब', "b"),
+    ('भ', "b"),
+    ('म', "m"),
+    ('य', "i"),
+    ('र', "r"),
+    ('ल', "l"),
+    ('व', "v"),
+    ('श', "c"),
+    ('ष', "c"),
+    ('स', "s"),
+    ('ह', "'"),
+    ('ळ', "l"),
+];
+
+/// Consonants whose sound changes when followed by a nukta
+static NUKTA_CONSONANTS: [(char, &str); 7] = [
+    ('क', "k"),
+    ('ख', "x"),
+    ('ग', "g"),
+    ('ज', "z"),
+    ('फ', "f"),
+    ('ड', "r"),
+    ('ढ', "r"),
+];
+
+/// Precomposed consonants with nukta and the base consonant they stand for
+static PRECOMPOSED_NUKTA: [(char, char); 8] = [
+    ('\u{0958}', 'क'),
+    ('\u{0959}', 'ख'),
+    ('\u{095A}', 'ग'),
+    ('\u{095B}', 'ज'),
+    ('\u{095C}', 'ड'),
+    ('\u{095D}', 'ढ'),
+    ('\u{095E}', 'फ'),
+    ('\u{095F}', 'य'),
+];
+
+static INDEPENDENT_VOWELS: [(char, &str); 11] = [
+    ('अ', "a"),
+    ('आ', "a"),
+    ('इ', "i"),
+    ('ई', "i"),
+    ('उ', "u"),
+    ('ऊ', "u"),
+    ('ऋ', "ri"),
+    ('ए', "e"),
+    ('ऐ', "e"),
+    ('ओ', "o"),
+    ('औ', "o"),
+];
+
+static VOWEL_SIGNS: [(char, &str); 10] = [
+    ('ा', "a"),
+    ('ि', "i"),
+    ('ी', "i"),
+    ('ु', "u"),
+    ('ू', "u"),
+    ('ृ', "ri"),
+    ('े', "e"),
+    ('ै', "e"),
+    ('ो', "o"),
+    ('ौ', "o"),
+];
+
+const VIRAMA: char = '्';
+const NUKTA: char = '़';
+const ANUSVARA: char = 'ं';
+const CANDRABINDU: char = 'ँ';
+const VISARGA: char = 'ः';
+
+fn lookup(table: &[(char, &'static str)], c: char) -> Option<&'static str> {
+    table.iter().find(|&&(key, _)| key == c).map(|&(_, value)| value)
+}
+
+/// Piece of a transliterated word; inherent vowels are kept apart so that
+/// schwa deletion can drop them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Consonant(&'static str),
+    Vowel(&'static str),
+    InherentVowel,
+    Other(char),
+}
+
+impl Segment {
+    fn text(&self) -> String {
+        match self {
+            Segment::Consonant(letters) | Segment::Vowel(letters) => letters.to_string(),
+            Segment::InherentVowel => "a".to_string(),
+            Segment::Other(c) => c.to_string(),
+        }
+    }
+}
+
+/// Transliterate Devanagari Hindi into Lojban letters, deleting the
+/// inherent vowel at the end of a word and between a vowel and consonant on
+/// one side and a consonant and vowel on the other, as spoken Hindi does
+pub fn transliterate(word: &str) -> String {
+    let chars: Vec<char> = word
+        .chars()
+        .flat_map(|c| match PRECOMPOSED_NUKTA.iter().find(|&&(precomposed, _)| precomposed == c) {
+            Some(&(_, base)) => vec![base, NUKTA],
+            None => vec![c],
+        })
+        .collect();
+    let mut segments: Vec<Segment> = Vec::with_capacity(chars.len() * 2);
+    let mut inherent_vowel = false;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(consonant) = lookup(&CONSONANTS, c) {
+            if inherent_vowel {
+                segments.push(Segment::InherentVowel);
+            }
+            let consonant = if chars.get(i + 1) == Some(&NUKTA) {
+                i += 1;
+                lookup(&NUKTA_CONSONANTS, c).unwrap_or(consonant)
+            } else {
+                consonant
+            };
+            segments.push(Segment::Consonant(consonant));
+            inherent_vowel = true;
+        } else if let Some(vowel) = lookup(&VOWEL_SIGNS, c) {
+            segments.push(Segment::Vowel(vowel));
+            inherent_vowel = false;
+        } else if c == VIRAMA {
+            inherent_vowel = false;
+        } else {
+            if inherent_vowel {
+                segments.push(Segment::InherentVowel);
+                inherent_vowel = false;
+            }
+            if let Some(vowel) = lookup(&INDEPENDENT_VOWELS, c) {
+                segments.push(Segment::Vowel(vowel));
+            } else if c == ANUSVARA || c == CANDRABINDU {
+                segments.push(Segment::Consonant("n"));
+            } else if c == VISARGA {
+                segments.push(Segment::Other('\''));
+            } else if c.is_ascii_alphabetic() {
+                segments.push(Segment::Other(c.to_ascii_lowercase()));
+            }
+        }
+        i += 1;
+    }
+
+    let is_vowel = |segment: Option<&Segment>| matches!(segment, Some(Segment::Vowel(_) | Segment::InherentVowel));
+    if inherent_vowel && !segments.iter().any(|segment| is_vowel(Some(segment))) {
+        segments.push(Segment::InherentVowel);
+    }
+
+    // Scanning from the end keeps a deletion from opening the context of the
+    // schwa before it, as in `samajhna`
+    for k in (2..segments.len()).rev() {
+        let is_consonant = |j: usize| matches!(segments.get(j), Some(Segment::Consonant(_)));
+        if segments[k] == Segment::InherentVowel
+            && is_consonant(k - 1)
+            && is_vowel(segments.get(k - 2))
+            && is_consonant(k + 1)
+            && is_vowel(segments.get(k + 2))
+        {
+            segments.remove(k);
+        }
+    }
+
+    super::finish(&segments.iter().map(Segment::text).collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precomposed_nukta_matches_decomposed() {
+        assert_eq!(transliterate("\u{095B}मीन"), transliterate("ज\u{093C}मीन"));
+        assert_eq!(transliterate("\u{095E}िल्म"), transliterate("फ\u{093C}िल्म"));
+        assert!(transliterate("\u{095B}मीन").starts_with('z'));
+        assert!(transliterate("\u{095E}िल्म").starts_with('f'));
+    }
+
+    #[test]
+    fn deletes_final_and_medial_schwa() {
+        for (hindi, lojban) in [
+            ("कमला", "kamla"),
+            ("समझना", "samadjna"),
+            ("नमकीन", "namkin"),
+            ("कलम", "kalam"),
+            ("घर", "gar"),
+            ("न", "na"),
+            ("हिंदी", "'indi"),
+            ("भारत", "barat"),
+        ] {
+            assert_eq!(transliterate(hindi), lojban, "{}", hindi);
+        }
+    }
+}
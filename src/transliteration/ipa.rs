@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// IPA symbols with a direct Lojban counterpart
+static IPA_DIRECT: [(char, &str); 35] = [
+    ('a', "a"),
+    ('b', "b"),
+    ('d', "d"),
+    ('e', "e"),
+    ('f', "f"),
+    ('ɡ', "g"),
+    ('g', "g"),
+    ('h', "'"),
+    ('i', "i"),
+    ('j', "i"),
+    ('k', "k"),
+    ('l', "l"),
+    ('m', "m"),
+    ('n', "n"),
+    ('o', "o"),
+    ('p', "p"),
+    ('r', "r"),
+    ('ɾ', "r"),
+    ('ɹ', "r"),
+    ('s', "s"),
+    ('ʃ', "c"),
+    ('t', "t"),
+    ('u', "u"),
+    ('v', "v"),
+    ('w', "u"),
+    ('x', "x"),
+    ('z', "z"),
+    ('ʒ', "j"),
+    ('ə', "y"),
+    ('ɛ', "e"),
+    ('ɔ', "o"),
+    ('ɪ', "i"),
+    ('ʧ', "tc"),
+    ('ʤ', "dj"),
+    ('\'', "'"),
+];
+
+/// Closest Lojban phonemes for sounds Lojban lacks
+static IPA_FALLBACKS: [(char, &str); 55] = [
+    ('ɑ', "a"),
+    ('ɐ', "a"),
+    ('æ', "a"),
+    ('ʌ', "a"),
+    ('ɒ', "o"),
+    ('ɘ', "y"),
+    ('ɜ', "y"),
+    ('ɚ', "yr"),
+    ('ɝ', "yr"),
+    ('ɨ', "i"),
+    ('y', "i"),
+    ('ʏ', "i"),
+    ('ø', "e"),
+    ('œ', "e"),
+    ('ɵ', "o"),
+    ('ʊ', "u"),
+    ('ɯ', "u"),
+    ('ʉ', "u"),
+    ('ɤ', "o"),
+    ('θ', "t"),
+    ('ð', "d"),
+    ('ŋ', "n"),
+    ('ɱ', "m"),
+    ('ɲ', "n"),
+    ('ɳ', "n"),
+    ('ɫ', "l"),
+    ('ʎ', "l"),
+    ('ɭ', "l"),
+    ('ɬ', "l"),
+    ('ʁ', "r"),
+    ('ʀ', "r"),
+    ('ɽ', "r"),
+    ('ʈ', "t"),
+    ('ɖ', "d"),
+    ('c', "k"),
+    ('ɟ', "g"),
+    ('q', "k"),
+    ('ɢ', "g"),
+    ('ɣ', "g"),
+    ('χ', "x"),
+    ('ç', "x"),
+    ('ħ', "'"),
+    ('ɦ', "'"),
+    ('ʂ', "c"),
+    ('ɕ', "c"),
+    ('ʐ', "j"),
+    ('ʑ', "j"),
+    ('ʝ', "j"),
+    ('β', "v"),
+    ('ʋ', "v"),
+    ('ɸ', "f"),
+    ('ɥ', "u"),
+    ('ɰ', "u"),
+    ('ʔ', ""),
+    ('ʕ', ""),
+];
+
+/// Length, stress, tone and secondary articulation marks carry no phoneme
+const IPA_IGNORED: &str = "ːˑˈˌʰʲʷˠˤⁿˡ.‿|/[]˥˦˧˨˩";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// Sound that exists in Lojban
+    Direct,
+    /// Sound Lojban lacks, replaced by the closest phoneme
+    Fallback,
+    /// Diacritic or suprasegmental mark without a phoneme of its own
+    Ignored,
+    /// Symbol with no known mapping; dropped from the output
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpaSegment {
+    pub ipa: char,
+    pub lojban: String,
+    pub kind: SegmentKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpaConversion {
+    pub source: String,
+    pub output: String,
+    pub segments: Vec<IpaSegment>,
+}
+
+impl IpaConversion {
+    /// Segments worth showing to the user: fallbacks and unknown symbols
+    pub fn approximations(&self) -> impl Iterator<Item = &IpaSegment> {
+        self.segments
+            .iter()
+            .filter(|s| matches!(s.kind, SegmentKind::Fallback | SegmentKind::Unknown))
+    }
+}
+
+impl fmt::Display for IpaConversion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} → {}", self.source, self.output)?;
+        let notes: Vec<String> = self
+            .approximations()
+            .map(|s| match s.kind {
+                SegmentKind::Unknown => format!("{} dropped", s.ipa),
+                _ if s.lojban.is_empty() => format!("{} dropped", s.ipa),
+                _ => format!("{}→{}", s.ipa, s.lojban),
+            })
+            .collect();
+        if !notes.is_empty() {
+            write!(f, " [{}]", notes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts IPA transcriptions into the closest Lojban phonemes
+pub struct IpaConverter {
+    fallbacks: HashMap<char, String>,
+}
+
+impl Default for IpaConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IpaConverter {
+    pub fn new() -> Self {
+        Self {
+            fallbacks: IPA_FALLBACKS
+                .iter()
+                .map(|&(ipa, lojban)| (ipa, lojban.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Override the Lojban replacement used for a sound Lojban lacks; symbols
+    /// with a direct Lojban counterpart always map to it
+    pub fn with_fallback(mut self, ipa: char, lojban: &str) -> Self {
+        self.fallbacks.insert(ipa, lojban.to_string());
+        self
+    }
+
+    /// Apply overrides given as `θ=f,ð=v`; an empty right side drops the sound
+    pub fn with_fallbacks_str(self, spec: &str) -> anyhow::Result<Self> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .try_fold(self, |converter, pair| {
+                let (ipa, lojban) = pair
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("IPA fallback '{}' must look like 'θ=t'", pair))?;
+                let mut chars = ipa.trim().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(symbol), None) if IPA_DIRECT.iter().any(|&(key, _)| key == symbol) => {
+                        anyhow::bail!("'{}' has a direct Lojban counterpart and takes no fallback", symbol)
+                    }
+                    (Some(symbol), None) => Ok(converter.with_fallback(symbol, lojban.trim())),
+                    _ => anyhow::bail!("IPA fallback '{}' must map a single symbol", pair),
+                }
+            })
+    }
+
+    /// Convert an IPA transcription into Lojban letters
+    ///
+    /// # Arguments
+    /// * `word` - IPA transcription, with or without stress and length marks
+    ///
+    /// # Returns
+    /// Converted word along with the mapping applied to every symbol
+    pub fn convert(&self, word: &str) -> IpaConversion {
+        let segments: Vec<IpaSegment> = word
+            .chars()
+            .map(|ipa| {
+                let (lojban, kind) = self.lookup(ipa);
+                IpaSegment { ipa, lojban, kind }
+            })
+            .collect();
+
+        IpaConversion {
+            source: word.to_string(),
+            output: segments.iter().map(|s| s.lojban.as_str()).collect(),
+            segments,
+        }
+    }
+
+    fn lookup(&self, ipa: char) -> (String, SegmentKind) {
+        if let Some(&(_, lojban)) = IPA_DIRECT.iter().find(|&&(key, _)| key == ipa) {
+            return (lojban.to_string(), SegmentKind::Direct);
+        }
+        if let Some(lojban) = self.fallbacks.get(&ipa) {
+            return (lojban.clone(), SegmentKind::Fallback);
+        }
+        if IPA_IGNORED.contains(ipa) || is_combining_mark(ipa) {
+            return (String::new(), SegmentKind::Ignored);
+        }
+        (String::new(), SegmentKind::Unknown)
+    }
+}
+
+#[inline]
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036f}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(conversion: &IpaConversion) -> Vec<SegmentKind> {
+        conversion.segments.iter().map(|s| s.kind).collect()
+    }
+
+    #[test]
+    fn maps_direct_symbols() {
+        let converter = IpaConverter::new();
+        for (ipa, lojban) in [("ˈʃɔp", "cop"), ("ʧɛk", "tcek"), ("ʤæm", "djam"), ("la'i", "la'i")] {
+            assert_eq!(converter.convert(ipa).output, lojban, "{}", ipa);
+        }
+        let conversion = converter.convert("ʒu");
+        assert_eq!(kinds(&conversion), [SegmentKind::Direct, SegmentKind::Direct]);
+        assert_eq!(conversion.approximations().count(), 0);
+    }
+
+    #[test]
+    fn maps_fallbacks() {
+        let converter = IpaConverter::new();
+        for (ipa, lojban) in [("θɪŋ", "tin"), ("wɔtɚ", "uotyr"), ("bɝd", "byrd"), ("ɬan", "lan"), ("ɱ", "m"), ("ʝo", "jo")] {
+            assert_eq!(converter.convert(ipa).output, lojban, "{}", ipa);
+        }
+        let conversion = converter.convert("ðə");
+        assert_eq!(kinds(&conversion), [SegmentKind::Fallback, SegmentKind::Direct]);
+        assert_eq!(conversion.to_string(), "ðə → dy [ð→d]");
+    }
+
+    #[test]
+    fn reports_ignored_and_unknown_symbols() {
+        let conversion = IpaConverter::new().convert("ˈkʰa☃");
+        assert_eq!(conversion.output, "ka");
+        assert_eq!(
+            kinds(&conversion),
+            [SegmentKind::Ignored, SegmentKind::Direct, SegmentKind::Ignored, SegmentKind::Direct, SegmentKind::Unknown]
+        );
+        assert_eq!(conversion.to_string(), "ˈkʰa☃ → ka [☃ dropped]");
+    }
+
+    #[test]
+    fn overrides_fallbacks_only() {
+        let converter = IpaConverter::new().with_fallbacks_str("θ=f, ð=").unwrap();
+        assert_eq!(converter.convert("θɪðə").output, "fiy");
+        assert!(IpaConverter::new().with_fallbacks_str("h=x").is_err());
+        assert!(IpaConverter::new().with_fallbacks_str("θ").is_err());
+        assert!(IpaConverter::new().with_fallbacks_str("θθ=t").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::libs::config::{C, V};
+
+pub mod arabic;
+pub mod english;
+pub mod hindi;
+pub mod ipa;
+pub mod pinyin;
+pub mod russian;
+pub mod script;
+pub mod spanish;
+
+/// How a source word is turned into Lojban letters before scoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transliterator {
+    /// Word is already written in Lojban letters
+    None,
+    Ipa,
+    Pinyin,
+    Hindi,
+    Russian,
+    Arabic,
+    Spanish,
+    English,
+}
+
+impl Transliterator {
+    pub fn transliterate(&self, word: &str) -> String {
+        match self {
+            Transliterator::None => word.to_string(),
+            Transliterator::Ipa => ipa::IpaConverter::new().convert(word).output,
+            Transliterator::Pinyin => pinyin::transliterate(word),
+            Transliterator::Hindi => hindi::transliterate(word),
+            Transliterator::Russian => russian::transliterate(word),
+            Transliterator::Arabic => arabic::transliterate(word),
+            Transliterator::Spanish => spanish::transliterate(word),
+            Transliterator::English => english::transliterate(word),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transliterator::None => "none",
+            Transliterator::Ipa => "ipa",
+            Transliterator::Pinyin => "pinyin",
+            Transliterator::Hindi => "hindi",
+            Transliterator::Russian => "russian",
+            Transliterator::Arabic => "arabic",
+            Transliterator::Spanish => "spanish",
+            Transliterator::English => "english",
+        }
+    }
+}
+
+impl fmt::Display for Transliterator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Transliterator {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "-" | "none" | "lojban" => Ok(Transliterator::None),
+            "ipa" => Ok(Transliterator::Ipa),
+            "pinyin" | "mandarin" | "chinese" => Ok(Transliterator::Pinyin),
+            "hindi" | "devanagari" => Ok(Transliterator::Hindi),
+            "russian" | "cyrillic" => Ok(Transliterator::Russian),
+            "arabic" => Ok(Transliterator::Arabic),
+            "spanish" => Ok(Transliterator::Spanish),
+            "english" => Ok(Transliterator::English),
+            other => anyhow::bail!("Unknown transliterator '{}'", other),
+        }
+    }
+}
+
+/// Parse a comma-separated list of transliterators, one per input slot
+pub fn parse_transliterators(spec: &str) -> anyhow::Result<Vec<Transliterator>> {
+    spec.split(',').map(str::parse).collect()
+}
+
+/// Greedy longest-match rewrite; characters without a rule are kept as they are.
+/// The word is wrapped in `^` and `$` so rules can match at its edges.
+fn rewrite(word: &str, rules: &[(&str, &str)]) -> String {
+    let wrapped = format!("^{}$", word.to_lowercase());
+    let mut rest = wrapped.as_str();
+    let mut out = String::with_capacity(rest.len());
+
+    while let Some(c) = rest.chars().next() {
+        match rules
+            .iter()
+            .filter(|(from, _)| rest.starts_with(from))
+            .max_by_key(|(from, _)| from.len())
+        {
+            Some((from, to)) => {
+                out.push_str(to);
+                rest = &rest[from.len()..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    finish(&out)
+}
+
+/// Keep only Lojban letters and collapse doubled consonants
+fn finish(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars().filter(|&c| C.contains(c) || V.contains(c) || c == 'y' || c == '\'') {
+        if C.contains(c) && out.ends_with(c) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,78 @@
+use super::rewrite;
+
+/// Tone-marked vowels and their bare forms
+static TONE_MARKS: [(char, char); 25] = [
+    ('ā', 'a'), ('á', 'a'), ('ǎ', 'a'), ('à', 'a'),
+    ('ē', 'e'), ('é', 'e'), ('ě', 'e'), ('è', 'e'),
+    ('ī', 'i'), ('í', 'i'), ('ǐ', 'i'), ('ì', 'i'),
+    ('ō', 'o'), ('ó', 'o'), ('ǒ', 'o'), ('ò', 'o'),
+    ('ū', 'u'), ('ú', 'u'), ('ǔ', 'u'), ('ù', 'u'),
+    ('ǖ', 'ü'), ('ǘ', 'ü'), ('ǚ', 'ü'), ('ǜ', 'ü'),
+    ('ê', 'e'),
+];
+
+static PINYIN_RULES: [(&str, &str); 23] = [
+    ("zh", "dj"),
+    ("ch", "tc"),
+    ("sh", "c"),
+    ("ng", "n"),
+    ("z", "dz"),
+    ("c", "ts"),
+    ("j", "dj"),
+    ("q", "tc"),
+    ("x", "c"),
+    ("h", "x"),
+    ("y", "i"),
+    ("yi", "i"),
+    ("yu", "iu"),
+    ("ju", "djiu"),
+    ("qu", "tciu"),
+    ("xu", "ciu"),
+    ("w", "u"),
+    ("wu", "u"),
+    ("ü", "iu"),
+    ("v", "iu"),
+    ("ui", "uei"),
+    ("iu", "iou"),
+    ("'", ""),
+];
+
+/// Transliterate Hanyu Pinyin, with tone marks or tone numbers, into Lojban letters
+pub fn transliterate(word: &str) -> String {
+    let toneless: String = word
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            TONE_MARKS
+                .iter()
+                .find(|&&(marked, _)| marked == c)
+                .map_or(c, |&(_, bare)| bare)
+        })
+        .filter(|c| !c.is_ascii_digit())
+        .collect();
+
+    rewrite(&toneless, &PINYIN_RULES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_pinyin() {
+        for (pinyin, lojban) in [
+            ("zhōng", "djon"),
+            ("shu1", "cu"),
+            ("xuě", "ciue"),
+            ("jù", "djiu"),
+            ("qu4", "tciu"),
+            ("yú", "iu"),
+            ("lǜ", "liu"),
+            ("nv3", "niu"),
+            ("guì", "guei"),
+            ("xi'an", "cian"),
+        ] {
+            assert_eq!(transliterate(pinyin), lojban, "{}", pinyin);
+        }
+    }
+}
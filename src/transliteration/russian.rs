@@ -0,0 +1,71 @@
+use super::rewrite;
+
+static RUSSIAN_RULES: [(&str, &str); 41] = [
+    ("а", "a"),
+    ("б", "b"),
+    ("в", "v"),
+    ("г", "g"),
+    ("д", "d"),
+    ("е", "e"),
+    ("^е", "ie"),
+    ("ё", "io"),
+    ("ж", "j"),
+    ("з", "z"),
+    ("и", "i"),
+    ("й", "i"),
+    ("к", "k"),
+    ("л", "l"),
+    ("м", "m"),
+    ("н", "n"),
+    ("о", "o"),
+    ("п", "p"),
+    ("р", "r"),
+    ("с", "s"),
+    ("т", "t"),
+    ("у", "u"),
+    ("ф", "f"),
+    ("х", "x"),
+    ("ц", "ts"),
+    ("ч", "tc"),
+    ("ш", "c"),
+    ("щ", "c"),
+    ("ъ", ""),
+    ("ы", "i"),
+    ("ь", ""),
+    ("э", "e"),
+    ("ю", "iu"),
+    ("я", "ia"),
+    ("тся", "tsa"),
+    ("ться", "tsa"),
+    ("его$", "evo"),
+    ("^его$", "ievo"),
+    ("ого$", "ovo"),
+    ("ъе", "ie"),
+    ("ье", "ie"),
+];
+
+/// Transliterate Russian Cyrillic into Lojban letters
+pub fn transliterate(word: &str) -> String {
+    rewrite(word, &RUSSIAN_RULES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_russian() {
+        for (russian, lojban) in [
+            ("Ель", "iel"),
+            ("жёлтый", "jioltii"),
+            ("щука", "cuka"),
+            ("учиться", "utcitsa"),
+            ("его", "ievo"),
+            ("красного", "krasnovo"),
+            ("объект", "obiekt"),
+            ("юг", "iug"),
+        ] {
+            assert_eq!(transliterate(russian), lojban, "{}", russian);
+        }
+    }
+}
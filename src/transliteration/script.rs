@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::phonotactics::{is_consonant, is_diphthong, is_vowel};
+
+/// Names accepted by `Script::from_str`, default first
+pub const SCRIPT_NAMES: [&str; 3] = ["latin", "cyrillic", "zbalermorna"];
+
+/// Writing system for Lojban text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Script {
+    #[default]
+    Latin,
+    /// Community Cyrillic orthography, one letter per Latin letter
+    Cyrillic,
+    /// Zbalermorna in the CSUR Private Use Area, U+ED80 to U+EDBF
+    Zbalermorna,
+}
+
+impl Script {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Script::Latin => "latin",
+            Script::Cyrillic => "cyrillic",
+            Script::Zbalermorna => "zbalermorna",
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Script {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "latin" | "lojban" => Ok(Script::Latin),
+            "cyrillic" => Ok(Script::Cyrillic),
+            "zbalermorna" => Ok(Script::Zbalermorna),
+            other => anyhow::bail!(
+                "Unknown script '{}', expected one of: {}",
+                other,
+                SCRIPT_NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+static CYRILLIC: [(char, char); 24] = [
+    ('a', 'а'),
+    ('b', 'б'),
+    ('c', 'ш'),
+    ('d', 'д'),
+    ('e', 'е'),
+    ('f', 'ф'),
+    ('g', 'г'),
+    ('i', 'и'),
+    ('j', 'ж'),
+    ('k', 'к'),
+    ('l', 'л'),
+    ('m', 'м'),
+    ('n', 'н'),
+    ('o', 'о'),
+    ('p', 'п'),
+    ('r', 'р'),
+    ('s', 'с'),
+    ('t', 'т'),
+    ('u', 'у'),
+    ('v', 'в'),
+    ('x', 'х'),
+    ('y', 'ы'),
+    ('z', 'з'),
+    ('\'', 'ъ'),
+];
+
+/// Cyrillic spellings read back as Lojban letters besides those of `CYRILLIC`
+static CYRILLIC_ALIASES: [(char, char); 3] = [('э', 'e'), ('й', 'i'), ('ь', '\'')];
+
+/// Consonants, apostrophe and pauses of Zbalermorna
+static ZBALERMORNA_LETTERS: [(char, char); 20] = [
+    ('p', '\u{ED80}'),
+    ('t', '\u{ED81}'),
+    ('k', '\u{ED82}'),
+    ('f', '\u{ED83}'),
+    ('l', '\u{ED84}'),
+    ('s', '\u{ED85}'),
+    ('c', '\u{ED86}'),
+    ('m', '\u{ED87}'),
+    ('x', '\u{ED88}'),
+    ('.', '\u{ED89}'),
+    ('\'', '\u{ED8A}'),
+    ('b', '\u{ED90}'),
+    ('d', '\u{ED91}'),
+    ('g', '\u{ED92}'),
+    ('v', '\u{ED93}'),
+    ('r', '\u{ED94}'),
+    ('z', '\u{ED95}'),
+    ('j', '\u{ED96}'),
+    ('n', '\u{ED97}'),
+    (',', '\u{ED98}'),
+];
+
+/// Vowels and diphthongs with their diacritic form, written on a preceding
+/// consonant or apostrophe, and their full form, written on its own
+static ZBALERMORNA_VOWELS: [(&str, char, char); 10] = [
+    ("a", '\u{EDA1}', '\u{EDB1}'),
+    ("e", '\u{EDA2}', '\u{EDB2}'),
+    ("i", '\u{EDA3}', '\u{EDB3}'),
+    ("o", '\u{EDA4}', '\u{EDB4}'),
+    ("u", '\u{EDA5}', '\u{EDB5}'),
+    ("y", '\u{EDA6}', '\u{EDB6}'),
+    ("ai", '\u{EDA8}', '\u{EDB8}'),
+    ("ei", '\u{EDA9}', '\u{EDB9}'),
+    ("oi", '\u{EDAA}', '\u{EDBA}'),
+    ("au", '\u{EDAB}', '\u{EDBB}'),
+];
+
+/// Write Lojban text in another script; characters that are not Lojban
+/// letters are kept as they are
+pub fn to_script(text: &str, script: Script) -> String {
+    match script {
+        Script::Latin => text.to_string(),
+        Script::Cyrillic => text
+            .chars()
+            .map(|c| {
+                let lower = c.to_ascii_lowercase();
+                match CYRILLIC.iter().find(|(latin, _)| *latin == lower) {
+                    Some(&(_, cyrillic)) if c.is_uppercase() => cyrillic.to_uppercase().next().unwrap_or(cyrillic),
+                    Some(&(_, cyrillic)) => cyrillic,
+                    None => c,
+                }
+            })
+            .collect(),
+        Script::Zbalermorna => to_zbalermorna(&text.to_ascii_lowercase()),
+    }
+}
+
+/// Read text written in `script` back into Lojban letters
+pub fn from_script(text: &str, script: Script) -> String {
+    match script {
+        Script::Latin => text.to_string(),
+        Script::Cyrillic => text
+            .chars()
+            .map(|c| {
+                let lower = c.to_lowercase().next().unwrap_or(c);
+                let latin = CYRILLIC
+                    .iter()
+                    .map(|&(latin, cyrillic)| (cyrillic, latin))
+                    .chain(CYRILLIC_ALIASES.iter().copied())
+                    .find(|(cyrillic, _)| *cyrillic == lower)
+                    .map(|(_, latin)| latin);
+                match latin {
+                    Some(latin) if c.is_uppercase() => latin.to_ascii_uppercase(),
+                    Some(latin) => latin,
+                    None => c,
+                }
+            })
+            .collect(),
+        Script::Zbalermorna => text
+            .chars()
+            .map(|c| {
+                ZBALERMORNA_LETTERS
+                    .iter()
+                    .find(|(_, glyph)| *glyph == c)
+                    .map(|(latin, _)| latin.to_string())
+                    .or_else(|| {
+                        ZBALERMORNA_VOWELS
+                            .iter()
+                            .find(|(_, diacritic, full)| *diacritic == c || *full == c)
+                            .map(|(latin, _, _)| latin.to_string())
+                    })
+                    .unwrap_or_else(|| c.to_string())
+            })
+            .collect(),
+    }
+}
+
+fn to_zbalermorna(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() * 3);
+    let mut after_consonant = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_vowel(c) || c == 'y' {
+            let diphthong = chars.get(i + 1).is_some_and(|&n| is_diphthong(c, n))
+                && !chars.get(i + 2).is_some_and(|&n| is_vowel(n));
+            let len = if diphthong { 2 } else { 1 };
+            let vowel: String = chars[i..i + len].iter().collect();
+            if let Some(&(_, diacritic, full)) = ZBALERMORNA_VOWELS.iter().find(|(v, _, _)| *v == vowel) {
+                out.push(if after_consonant { diacritic } else { full });
+            }
+            after_consonant = false;
+            i += len;
+            continue;
+        }
+        match ZBALERMORNA_LETTERS.iter().find(|(latin, _)| *latin == c) {
+            Some(&(_, glyph)) => out.push(glyph),
+            None => out.push(c),
+        }
+        after_consonant = is_consonant(c) || c == '\'';
+        i += 1;
+    }
+    out
+}
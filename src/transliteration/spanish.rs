@@ -0,0 +1,71 @@
+use super::rewrite;
+
+static SPANISH_RULES: [(&str, &str); 31] = [
+    ("á", "a"),
+    ("é", "e"),
+    ("í", "i"),
+    ("ó", "o"),
+    ("ú", "u"),
+    ("ü", "u"),
+    ("ñ", "ni"),
+    ("ch", "tc"),
+    ("ll", "i"),
+    ("rr", "r"),
+    ("qu", "k"),
+    ("gue", "ge"),
+    ("gué", "ge"),
+    ("gui", "gi"),
+    ("guí", "gi"),
+    ("ce", "se"),
+    ("cé", "se"),
+    ("ci", "si"),
+    ("cí", "si"),
+    ("c", "k"),
+    ("ge", "xe"),
+    ("gé", "xe"),
+    ("gi", "xi"),
+    ("gí", "xi"),
+    ("j", "x"),
+    ("h", ""),
+    ("v", "b"),
+    ("w", "u"),
+    ("x", "ks"),
+    ("y", "i"),
+    ("z", "s"),
+];
+
+/// Words whose `x` keeps its old sound /x/, as in México and Oaxaca
+static X_AS_J: [&str; 4] = ["méxic", "mexic", "oaxac", "texas"];
+
+/// Transliterate Spanish orthography into Lojban letters (Latin American
+/// pronunciation: seseo and yeísmo)
+pub fn transliterate(word: &str) -> String {
+    let word = word.to_lowercase();
+    if X_AS_J.iter().any(|stem| word.contains(stem)) {
+        return rewrite(&word.replace('x', "j"), &SPANISH_RULES);
+    }
+    rewrite(&word, &SPANISH_RULES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterates_spanish() {
+        for (spanish, lojban) in [
+            ("México", "mexiko"),
+            ("mexicano", "mexikano"),
+            ("Oaxaca", "oaxaka"),
+            ("examen", "eksamen"),
+            ("guerra", "gera"),
+            ("cielo", "sielo"),
+            ("gente", "xente"),
+            ("llama", "iama"),
+            ("niño", "ninio"),
+            ("queso", "keso"),
+        ] {
+            assert_eq!(transliterate(spanish), lojban, "{}", spanish);
+        }
+    }
+}